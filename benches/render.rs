@@ -0,0 +1,83 @@
+//! Timings for the rendering hot paths, run against in-memory buffers and the
+//! fake backend so no terminal is involved. Run with `cargo bench`, optionally
+//! followed by a substring of the benchmark names to run.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use hex_editor::editor::Editor;
+use hex_editor::fake::TestBackend;
+
+const MEASURE_FOR: Duration = Duration::from_secs(2);
+
+/// Counts allocations so the benches can report them next to the timings
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn bench(filter: &Option<String>, name: &str, mut f: impl FnMut()) {
+    if filter.as_ref().is_some_and(|filter| !name.contains(filter)) {
+        return;
+    }
+    // Warm up caches (and the allocator) before measuring
+    f();
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let started = Instant::now();
+    let mut iterations = 0u32;
+    while started.elapsed() < MEASURE_FOR {
+        f();
+        iterations += 1;
+    }
+    let per_iteration = started.elapsed() / iterations;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - allocations) / iterations as usize;
+    println!(
+        "{name:<32} {per_iteration:>12.2?}/iter {allocations:>6} allocs/iter ({iterations} iterations)"
+    );
+}
+
+fn key(c: char) -> Event {
+    Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+}
+
+fn main() {
+    // cargo passes --bench, anything else is a name filter
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+
+    let content: Vec<u8> = (0..64 << 20).map(|i: u32| (i % 251) as u8).collect();
+    let editor = Editor::new(content);
+    let mut backend = TestBackend::new(200, 60);
+    bench(&filter, "draw full screen 200x60", || {
+        editor.draw(&mut backend).unwrap();
+        black_box(&backend);
+    });
+
+    // Zeroed memory is handed out lazily by the OS, only the rows we look at get touched
+    let mut editor = Editor::new(vec![0; 1 << 30]);
+    let mut backend = TestBackend::new(120, 50);
+    // Park the cursor on the last row so every j scrolls
+    for _ in 0..50 {
+        editor.handle_event(&mut backend, key('j')).unwrap();
+    }
+    bench(&filter, "scroll 1 GiB buffer by one line", || {
+        editor.handle_event(&mut backend, key('j')).unwrap();
+        black_box(&backend);
+    });
+}
@@ -0,0 +1,318 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use crate::editor::Wheel;
+use crate::error::{Error, Result};
+use crate::fields::{self, FieldSpec};
+use crate::input::Input;
+use crate::printable::Printable;
+
+pub const USAGE: &str = concat!(
+    "usage: hex-editor [--log-file PATH] [--size WxH] [--struct LAYOUT]\n",
+    "                  [--printable ascii|latin1|all] [--wheel LINES|cursor]\n",
+    "                  [--baseline PATH] [--keys PATH] [--ruler BYTES]\n",
+    "                  [--on-load COMMAND] [--offset-both] [--status FORMAT]\n",
+    "                  [--no-title] [--record-size BYTES] [--layout ascii-left]\n",
+    "                  FILE|-|--fd N\n",
+    "       hex-editor --help-keys",
+);
+
+/// Options given on the command line
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Args {
+    /// What to read the data from, `-` for stdin
+    pub input: Input,
+    /// Where to write the debug log, if anywhere
+    pub log_file: Option<PathBuf>,
+    /// Terminal size to use instead of asking the terminal, as (columns, rows)
+    pub size: Option<(u16, u16)>,
+    /// Fields to decode the bytes at the cursor as, like `u32 le, u8[6]`
+    pub fields: Vec<FieldSpec>,
+    /// Which bytes the ASCII pane shows as characters
+    pub printable: Printable,
+    /// What the mouse wheel does
+    pub wheel: Wheel,
+    /// File to compare against, differing bytes are tinted
+    pub baseline: Option<PathBuf>,
+    /// Script of keys to press before reading the keyboard
+    pub keys: Option<PathBuf>,
+    /// Distance between the offsets the ruler highlights, 0 turns it off
+    pub ruler: Option<usize>,
+    /// Shell command the file is piped through after it's read, e.g. to
+    /// decompress it
+    pub on_load: Option<String>,
+    /// Format of the status line, see `status::StatusFormat`
+    pub status: Option<String>,
+    /// Size of the records `:record` counts in
+    pub record_size: Option<usize>,
+    /// Leave the window title alone, for multiplexers that manage it
+    pub no_title: bool,
+    /// Repeat the offset after the ASCII pane
+    pub offset_both: bool,
+    /// Draw the ASCII pane before the hex pane, `--layout ascii-left`
+    pub ascii_left: bool,
+    /// Print the key bindings and exit, there's no file then
+    pub help_keys: bool,
+}
+
+impl Args {
+    /// Parses the arguments following the program name
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Args> {
+        let mut parsed = Args::default();
+        let mut path = None;
+        let mut fd = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(text) = arg.to_str() else {
+                path = Some(PathBuf::from(arg));
+                continue;
+            };
+            // Accept both `--flag value` and `--flag=value`
+            let (flag, inline_value) = match text.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.into())),
+                _ => (text, None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| Error::Parse(format!("{flag} needs a value\n{USAGE}")))
+            };
+            match flag {
+                "--log-file" => parsed.log_file = Some(PathBuf::from(value()?)),
+                "--baseline" => parsed.baseline = Some(PathBuf::from(value()?)),
+                "--keys" => parsed.keys = Some(PathBuf::from(value()?)),
+                "--size" => parsed.size = Some(parse_size(&text_value(flag, value()?)?)?),
+                "--struct" => parsed.fields = fields::parse_layout(&text_value(flag, value()?)?)?,
+                "--printable" => {
+                    let name = text_value(flag, value()?)?;
+                    parsed.printable = Printable::parse(&name).ok_or_else(|| {
+                        Error::Parse(format!("--printable must be one of {}", Printable::NAMES))
+                    })?
+                }
+                "--ruler" => parsed.ruler = Some(parse_number(&text_value(flag, value()?)?)?),
+                "--on-load" => parsed.on_load = Some(text_value(flag, value()?)?),
+                "--offset-both" => parsed.offset_both = true,
+                "--layout" => {
+                    parsed.ascii_left = match text_value(flag, value()?)?.as_str() {
+                        "ascii-left" => true,
+                        "hex-left" => false,
+                        other => {
+                            return Err(Error::Parse(format!(
+                                "--layout must be ascii-left or hex-left, not '{other}'"
+                            )))
+                        }
+                    }
+                }
+                "--no-title" => parsed.no_title = true,
+                "--record-size" => {
+                    parsed.record_size = Some(parse_number(&text_value(flag, value()?)?)?)
+                }
+                "--fd" => fd = Some(parse_number(&text_value(flag, value()?)?)?),
+                "--status" => parsed.status = Some(text_value(flag, value()?)?),
+                "--help-keys" => parsed.help_keys = true,
+                "--wheel" => parsed.wheel = parse_wheel(&text_value(flag, value()?)?)?,
+                "--" => {
+                    path = args.next().map(PathBuf::from);
+                    break;
+                }
+                _ if flag.starts_with("--") => {
+                    return Err(Error::Parse(format!("unknown option {flag}\n{USAGE}")))
+                }
+                _ => path = Some(PathBuf::from(arg)),
+            }
+        }
+
+        match (path, fd) {
+            (Some(_), Some(_)) => {
+                return Err(Error::Parse(format!(
+                    "give either a file or --fd, not both\n{USAGE}"
+                )))
+            }
+            (Some(path), None) if path.as_os_str() == "-" => parsed.input = Input::Stdin,
+            (Some(path), None) => parsed.input = Input::Path(path),
+            (None, Some(fd)) => parsed.input = Input::Fd(fd),
+            (None, None) if parsed.help_keys => {}
+            (None, None) => return Err(Error::Parse(USAGE.to_string())),
+        }
+        Ok(parsed)
+    }
+}
+
+fn text_value(flag: &str, value: OsString) -> Result<String> {
+    value
+        .into_string()
+        .map_err(|_| Error::Parse(format!("{flag} needs a text value")))
+}
+
+/// Parses a terminal size written as `COLUMNSxROWS`, e.g. `80x24`
+pub fn parse_size(text: &str) -> Result<(u16, u16)> {
+    let error = || Error::Parse(format!("invalid size '{text}', expected WxH like 80x24"));
+    let (columns, rows) = text.split_once(['x', 'X']).ok_or_else(error)?;
+    let columns = columns.trim().parse().map_err(|_| error())?;
+    let rows = rows.trim().parse().map_err(|_| error())?;
+    Ok((columns, rows))
+}
+
+/// Parses a byte count, in decimal or in hex with a `0x` prefix
+pub fn parse_number(text: &str) -> Result<usize> {
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| Error::Parse(format!("invalid number '{text}'")))
+}
+
+/// Parses `--wheel`, either the number of lines to scroll or `cursor` for
+/// moving the cursor instead
+fn parse_wheel(text: &str) -> Result<Wheel> {
+    if text == "cursor" {
+        return Ok(Wheel::MoveCursor);
+    }
+    match text.parse() {
+        Ok(lines) if lines > 0 => Ok(Wheel::Scroll(lines)),
+        _ => Err(Error::Parse(format!(
+            "invalid --wheel '{text}', expected a number of lines or 'cursor'"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args> {
+        Args::parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn path_only() {
+        let args = parse(&["file.bin"]).unwrap();
+        assert_eq!(args.input, Input::Path(PathBuf::from("file.bin")));
+        assert_eq!(args.log_file, None);
+    }
+
+    #[test]
+    fn log_file_in_both_forms() {
+        let args = parse(&["--log-file", "hex.log", "file.bin"]).unwrap();
+        assert_eq!(args.log_file, Some(PathBuf::from("hex.log")));
+        let args = parse(&["file.bin", "--log-file=hex.log"]).unwrap();
+        assert_eq!(args.log_file, Some(PathBuf::from("hex.log")));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["file.bin", "--log-file"]).is_err());
+        assert!(parse(&["--nope", "file.bin"]).is_err());
+    }
+
+    #[test]
+    fn size_override() {
+        let args = parse(&["--size", "80x24", "file.bin"]).unwrap();
+        assert_eq!(args.size, Some((80, 24)));
+        assert_eq!(parse_size("132X50").unwrap(), (132, 50));
+        for bad in ["80", "x24", "80x", "80x24x1", "-1x5", "99999x1"] {
+            assert!(parse_size(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn struct_layout() {
+        let args = parse(&["--struct=u16 be, u8[2]", "file.bin"]).unwrap();
+        assert_eq!(args.fields.len(), 2);
+        assert!(parse(&["--struct", "u17", "file.bin"]).is_err());
+    }
+
+    #[test]
+    fn printable() {
+        assert_eq!(parse(&["f"]).unwrap().printable, Printable::Ascii);
+        let args = parse(&["--printable", "latin1", "f"]).unwrap();
+        assert_eq!(args.printable, Printable::Latin1);
+        assert!(parse(&["--printable=utf8", "f"]).is_err());
+    }
+
+    #[test]
+    fn wheel() {
+        assert_eq!(parse(&["f"]).unwrap().wheel, Wheel::Scroll(3));
+        assert_eq!(parse(&["--wheel=5", "f"]).unwrap().wheel, Wheel::Scroll(5));
+        let args = parse(&["--wheel", "cursor", "f"]).unwrap();
+        assert_eq!(args.wheel, Wheel::MoveCursor);
+        for bad in ["0", "-1", "fast"] {
+            assert!(parse(&["--wheel", bad, "f"]).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn ruler() {
+        assert_eq!(parse(&["f"]).unwrap().ruler, None);
+        assert_eq!(parse(&["--ruler", "64", "f"]).unwrap().ruler, Some(64));
+        assert_eq!(parse(&["--ruler=0x100", "f"]).unwrap().ruler, Some(256));
+        assert!(parse(&["--ruler", "0xq", "f"]).is_err());
+    }
+
+    #[test]
+    fn layout() {
+        assert!(!parse(&["f"]).unwrap().ascii_left);
+        assert!(parse(&["--layout", "ascii-left", "f"]).unwrap().ascii_left);
+        assert!(!parse(&["--layout=hex-left", "f"]).unwrap().ascii_left);
+        assert!(parse(&["--layout", "left", "f"]).is_err());
+    }
+
+    #[test]
+    fn on_load() {
+        assert_eq!(parse(&["f"]).unwrap().on_load, None);
+        let args = parse(&["--on-load", "gzip -d", "f"]).unwrap();
+        assert_eq!(args.on_load.as_deref(), Some("gzip -d"));
+    }
+
+    #[test]
+    fn status_format() {
+        let args = parse(&["--status={offset} {size}", "f"]).unwrap();
+        assert_eq!(args.status.as_deref(), Some("{offset} {size}"));
+    }
+
+    #[test]
+    fn offset_both() {
+        assert!(!parse(&["f"]).unwrap().offset_both);
+        assert!(parse(&["f", "--offset-both"]).unwrap().offset_both);
+    }
+
+    #[test]
+    fn record_size() {
+        assert_eq!(parse(&["f"]).unwrap().record_size, None);
+        let args = parse(&["--record-size=0x20", "f"]).unwrap();
+        assert_eq!(args.record_size, Some(32));
+        assert!(parse(&["--record-size", "big", "f"]).is_err());
+    }
+
+    #[test]
+    fn inputs() {
+        assert_eq!(parse(&["-"]).unwrap().input, Input::Stdin);
+        assert_eq!(parse(&["--fd", "3"]).unwrap().input, Input::Fd(3));
+        assert!(parse(&["--fd", "3", "f"]).is_err());
+        assert!(parse(&["--fd", "three"]).is_err());
+        // A file called `-` can still be opened as ./-
+        let args = parse(&["./-"]).unwrap();
+        assert_eq!(args.input, Input::Path(PathBuf::from("./-")));
+    }
+
+    #[test]
+    fn no_title() {
+        assert!(!parse(&["f"]).unwrap().no_title);
+        assert!(parse(&["--no-title", "f"]).unwrap().no_title);
+    }
+
+    #[test]
+    fn help_keys_needs_no_file() {
+        assert!(parse(&["--help-keys"]).unwrap().help_keys);
+        assert!(!parse(&["f"]).unwrap().help_keys);
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let args = parse(&["--", "--log-file"]).unwrap();
+        assert_eq!(args.input, Input::Path(PathBuf::from("--log-file")));
+    }
+}
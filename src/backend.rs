@@ -0,0 +1,255 @@
+use std::io::{stdout, Stdout, Write};
+use std::time::Duration;
+
+use crossterm::cursor::MoveTo;
+use crossterm::event::{poll, read, Event};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{self, window_size};
+use crossterm::QueueableCommand;
+
+/// How a piece of text should look. `None` leaves the terminal's default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+}
+
+/// Everything the editor needs from the screen it draws on.
+pub trait Backend {
+    /// Size of the screen as (columns, rows)
+    fn size(&self) -> std::io::Result<(u16, u16)>;
+    fn move_to(&mut self, col: u16, row: u16) -> std::io::Result<()>;
+    /// Writes `text` at the cursor position, advancing the cursor
+    fn print(&mut self, text: &str, style: Style) -> std::io::Result<()>;
+    fn flush(&mut self) -> std::io::Result<()>;
+}
+
+/// Where the editor gets its input from.
+pub trait EventSource {
+    /// Waits up to `timeout` for the next event, `None` if nothing arrived in time
+    fn next_event(&mut self, timeout: Duration) -> std::io::Result<Option<Event>>;
+}
+
+/// Draws on the real terminal. Output is queued until `flush`.
+pub struct CrosstermBackend {
+    out: Stdout,
+    size_override: Option<(u16, u16)>,
+}
+
+impl CrosstermBackend {
+    pub fn new() -> Self {
+        CrosstermBackend {
+            out: stdout(),
+            size_override: None,
+        }
+    }
+
+    /// Uses `size` as the terminal size instead of asking the terminal
+    pub fn with_size(mut self, size: Option<(u16, u16)>) -> Self {
+        self.size_override = size;
+        self
+    }
+}
+
+/// Asks the terminal for its size as (columns, rows). `window_size` doesn't
+/// work everywhere (some Windows consoles and pseudo-terminals), so this
+/// falls back to `terminal::size` and then to `$COLUMNS`/`$LINES`.
+pub fn terminal_size() -> std::io::Result<(u16, u16)> {
+    let error = match window_size() {
+        Ok(size) if size.columns > 0 && size.rows > 0 => return Ok((size.columns, size.rows)),
+        Ok(_) => std::io::Error::other("terminal reported a size of zero"),
+        Err(err) => err,
+    };
+    log::debug!("window_size failed: {error}");
+    match terminal::size() {
+        Ok((columns, rows)) if columns > 0 && rows > 0 => return Ok((columns, rows)),
+        Ok(_) => {}
+        Err(err) => log::debug!("terminal::size failed: {err}"),
+    }
+    size_from_env(
+        std::env::var("COLUMNS").ok().as_deref(),
+        std::env::var("LINES").ok().as_deref(),
+    )
+    .ok_or(error)
+}
+
+fn size_from_env(columns: Option<&str>, lines: Option<&str>) -> Option<(u16, u16)> {
+    let columns = columns?.trim().parse().ok().filter(|&c: &u16| c > 0)?;
+    let lines = lines?.trim().parse().ok().filter(|&l: &u16| l > 0)?;
+    Some((columns, lines))
+}
+
+impl Default for CrosstermBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for CrosstermBackend {
+    fn size(&self) -> std::io::Result<(u16, u16)> {
+        match self.size_override {
+            Some(size) => Ok(size),
+            None => terminal_size(),
+        }
+    }
+
+    fn move_to(&mut self, col: u16, row: u16) -> std::io::Result<()> {
+        self.out.queue(MoveTo(col, row))?;
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str, style: Style) -> std::io::Result<()> {
+        if style == Style::default() {
+            self.out.queue(Print(text))?;
+            return Ok(());
+        }
+        if let Some(fg) = style.fg {
+            self.out.queue(SetForegroundColor(fg))?;
+        }
+        if let Some(bg) = style.bg {
+            self.out.queue(SetBackgroundColor(bg))?;
+        }
+        self.out.queue(Print(text))?.queue(ResetColor)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Reads events from the real terminal.
+pub struct CrosstermEvents;
+
+impl EventSource for CrosstermEvents {
+    fn next_event(&mut self, timeout: Duration) -> std::io::Result<Option<Event>> {
+        if poll(timeout)? {
+            Ok(Some(read()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// A character on a `MemoryBackend` screen and how it looks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: char,
+    pub style: Style,
+}
+
+impl Default for Cell {
+    fn default() -> Self {
+        Cell {
+            symbol: ' ',
+            style: Style::default(),
+        }
+    }
+}
+
+/// A screen that keeps every cell in memory, for drawing off screen and for
+/// tests. Text printed past the right edge is dropped, like a terminal with
+/// autowrap turned off.
+pub struct MemoryBackend {
+    cols: u16,
+    rows: u16,
+    cells: Vec<Cell>,
+    cursor: (u16, u16),
+    flushes: usize,
+}
+
+impl MemoryBackend {
+    pub fn new(cols: u16, rows: u16) -> Self {
+        MemoryBackend {
+            cols,
+            rows,
+            cells: vec![Cell::default(); cols as usize * rows as usize],
+            cursor: (0, 0),
+            flushes: 0,
+        }
+    }
+
+    /// Changes the size of the screen, dropping its contents like a real
+    /// terminal would leave them undefined
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        *self = MemoryBackend {
+            cursor: self.cursor,
+            flushes: self.flushes,
+            ..MemoryBackend::new(cols, rows)
+        };
+    }
+
+    pub fn cursor(&self) -> (u16, u16) {
+        self.cursor
+    }
+
+    /// Number of times the screen was flushed, i.e. frames drawn
+    pub fn flushes(&self) -> usize {
+        self.flushes
+    }
+
+    pub fn cell(&self, col: u16, row: u16) -> Cell {
+        self.cells[row as usize * self.cols as usize + col as usize]
+    }
+
+    pub fn row_text(&self, row: u16) -> String {
+        (0..self.cols)
+            .map(|col| self.cell(col, row).symbol)
+            .collect()
+    }
+
+    /// The cells of every row, top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.cells.chunks(self.cols.max(1) as usize)
+    }
+
+    /// Every row of the screen, with trailing spaces removed
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.rows)
+            .map(|row| self.row_text(row).trim_end().to_string())
+            .collect()
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn size(&self) -> std::io::Result<(u16, u16)> {
+        Ok((self.cols, self.rows))
+    }
+
+    fn move_to(&mut self, col: u16, row: u16) -> std::io::Result<()> {
+        self.cursor = (col, row);
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str, style: Style) -> std::io::Result<()> {
+        for symbol in text.chars() {
+            let (col, row) = self.cursor;
+            if col < self.cols && row < self.rows {
+                self.cells[row as usize * self.cols as usize + col as usize] =
+                    Cell { symbol, style };
+            }
+            self.cursor.0 = col.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn size_from_env_needs_both() {
+        assert_eq!(size_from_env(Some("80"), Some("24")), Some((80, 24)));
+        assert_eq!(size_from_env(Some(" 80 "), Some("24\n")), Some((80, 24)));
+        assert_eq!(size_from_env(Some("80"), None), None);
+        assert_eq!(size_from_env(None, Some("24")), None);
+        assert_eq!(size_from_env(Some("0"), Some("24")), None);
+        assert_eq!(size_from_env(Some("wide"), Some("24")), None);
+    }
+}
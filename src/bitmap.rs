@@ -0,0 +1,213 @@
+//! `:bitmap`, the data drawn as a picture for a quick look at images, fonts
+//! and tiles. Every character is a pixel wide and two tall, with half
+//! blocks for the top and bottom pixel.
+
+use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::style::Color;
+
+use crate::backend::Style;
+use crate::help::HelpStep;
+use crate::keymap::{self, Action};
+
+/// What a pixel is made of
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Depth {
+    /// A bit, most significant first, set bits lit
+    Bit,
+    /// A byte, as a shade of grey from black at 0 to white at 0xff
+    Byte,
+}
+
+/// The open bitmap screen
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BitmapView {
+    /// Pixels per row of the picture
+    width: usize,
+    depth: Depth,
+    /// Offset of the byte the picture starts at
+    offset: usize,
+    /// Rows of characters the whole picture takes
+    rows: usize,
+    /// First row of characters shown
+    first: usize,
+}
+
+impl BitmapView {
+    /// The picture of the bytes from `offset` in data `len` bytes long
+    pub fn new(width: usize, depth: Depth, offset: usize, len: usize) -> Self {
+        let bytes = len.saturating_sub(offset);
+        let pixels = match depth {
+            Depth::Bit => bytes * 8,
+            Depth::Byte => bytes,
+        };
+        let width = width.max(1);
+        BitmapView {
+            width,
+            depth,
+            offset,
+            rows: pixels.div_ceil(width).div_ceil(2),
+            first: 0,
+        }
+    }
+
+    /// Row `row` of the screen, at most `columns` characters of it
+    pub fn row(&self, content: &[u8], row: usize, columns: usize) -> Vec<(char, Style)> {
+        let y = (self.first + row) * 2;
+        (0..self.width.min(columns))
+            .map(|x| {
+                let top = self.pixel(content, x, y);
+                let bottom = self.pixel(content, x, y + 1);
+                cell(self.depth, top, bottom)
+            })
+            .collect()
+    }
+
+    /// The value of the pixel at `x`, `y`, `None` past the end of the data
+    fn pixel(&self, content: &[u8], x: usize, y: usize) -> Option<u8> {
+        let index = y.checked_mul(self.width)?.checked_add(x)?;
+        match self.depth {
+            Depth::Bit => {
+                let byte = content.get(self.offset.checked_add(index / 8)?)?;
+                Some(byte >> (7 - index % 8) & 1)
+            }
+            Depth::Byte => content.get(self.offset.checked_add(index)?).copied(),
+        }
+    }
+
+    pub fn status(&self) -> String {
+        let depth = match self.depth {
+            Depth::Bit => "a bit",
+            Depth::Byte => "a byte",
+        };
+        format!(
+            "bitmap from {:#x}, {} pixels wide, {depth} each; j/k scroll, any other key closes",
+            self.offset, self.width
+        )
+    }
+
+    /// Moves by `rows` rows of characters, keeping a screen of `screen_rows`
+    /// filled
+    pub fn scroll(&mut self, rows: isize, screen_rows: usize) -> HelpStep {
+        let max_first = self.rows.saturating_sub(screen_rows);
+        let old_first = self.first;
+        self.first = self.first.saturating_add_signed(rows).min(max_first);
+        if self.first == old_first {
+            HelpStep::Status
+        } else {
+            HelpStep::Redraw
+        }
+    }
+
+    /// Reacts to `key` on a screen with `rows` rows for the picture
+    pub fn key(&mut self, key: &KeyEvent, rows: usize) -> HelpStep {
+        let page = rows.max(1) as isize;
+        match key.code {
+            KeyCode::Char(' ') | KeyCode::PageDown => return self.scroll(page, rows),
+            KeyCode::PageUp => return self.scroll(-page, rows),
+            _ => {}
+        }
+        match keymap::action_for(key) {
+            Some(Action::Down) => self.scroll(1, rows),
+            Some(Action::Up) => self.scroll(-1, rows),
+            Some(Action::Top) => self.scroll(isize::MIN, rows),
+            Some(Action::Bottom) => self.scroll(isize::MAX, rows),
+            _ => HelpStep::Close,
+        }
+    }
+}
+
+/// The character for a pixel over another
+fn cell(depth: Depth, top: Option<u8>, bottom: Option<u8>) -> (char, Style) {
+    match depth {
+        Depth::Bit => {
+            let lit = |pixel: Option<u8>| pixel == Some(1);
+            let c = match (lit(top), lit(bottom)) {
+                (false, false) => ' ',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                (true, true) => '█',
+            };
+            (c, Style::default())
+        }
+        Depth::Byte => {
+            let Some(top) = top else {
+                return (' ', Style::default());
+            };
+            let style = Style {
+                fg: Some(grey(top)),
+                bg: bottom.map(grey),
+            };
+            ('▀', style)
+        }
+    }
+}
+
+/// The nearest of the 24 greys of 256 color terminals, with black and white
+/// at the ends
+fn grey(value: u8) -> Color {
+    match value {
+        0..=3 => Color::AnsiValue(16),
+        0xfc.. => Color::AnsiValue(231),
+        _ => Color::AnsiValue(232 + ((value as u16 - 4) * 24 / 248) as u8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(row: Vec<(char, Style)>) -> String {
+        row.into_iter().map(|(c, _)| c).collect()
+    }
+
+    #[test]
+    fn bits_are_half_blocks() {
+        let content = [0b1100_0000, 0b1010_0000, 0xff];
+        let view = BitmapView::new(4, Depth::Bit, 0, content.len());
+        // 24 pixels make 6 rows of 4, so 3 rows of characters
+        assert_eq!(view.rows, 3);
+        assert_eq!(text(view.row(&content, 0, 80)), "▀▀  ");
+        assert_eq!(text(view.row(&content, 1, 80)), "▀ ▀ ");
+        assert_eq!(text(view.row(&content, 2, 80)), "████");
+        assert_eq!(text(view.row(&content, 3, 80)), "    ");
+        assert_eq!(text(view.row(&content, 0, 3)), "▀▀ ");
+    }
+
+    #[test]
+    fn bytes_are_greys() {
+        let content = [0, 0xff, 0x80];
+        let view = BitmapView::new(2, Depth::Byte, 1, content.len());
+        let row = view.row(&content, 0, 80);
+        assert_eq!(
+            row[0],
+            (
+                '▀',
+                Style {
+                    fg: Some(Color::AnsiValue(231)),
+                    bg: None,
+                }
+            )
+        );
+        assert_eq!(row[1].1.fg, Some(grey(0x80)));
+        assert_eq!(grey(0), Color::AnsiValue(16));
+        assert_eq!(grey(4), Color::AnsiValue(232));
+        assert_eq!(grey(0xfb), Color::AnsiValue(255));
+    }
+
+    #[test]
+    fn scrolls_within_the_picture() {
+        let mut view = BitmapView::new(8, Depth::Bit, 0, 10);
+        assert_eq!(view.rows, 5);
+        assert_eq!(view.scroll(10, 2), HelpStep::Redraw);
+        assert_eq!(view.first, 3);
+        assert_eq!(view.scroll(1, 2), HelpStep::Status);
+        view.scroll(isize::MIN, 2);
+        assert_eq!(view.first, 0);
+    }
+
+    #[test]
+    fn huge_widths_are_blank_not_overflows() {
+        let view = BitmapView::new(usize::MAX, Depth::Byte, usize::MAX - 1, 4);
+        assert_eq!(text(view.row(&[1, 2, 3, 4], 1, 4)), "    ");
+    }
+}
@@ -0,0 +1,374 @@
+//! Commands typed on the `:` command line.
+
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::args::parse_number;
+use crate::bitmap::Depth;
+use crate::error::{Error, Result};
+use crate::interest::Kinds;
+
+/// Longest line `:cols` accepts
+pub const MAX_LINE_LENGTH: usize = 256;
+
+/// Widest picture `:bitmap` draws, in pixels
+pub const MAX_BITMAP_WIDTH: usize = 4096;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Quit,
+    Help,
+    /// Shows the recent status messages
+    Messages,
+    /// Moves the cursor to a byte offset
+    Goto(usize),
+    /// Moves the cursor to the start of a record
+    Record(usize),
+    /// Changes the size of the records `Record` counts in
+    RecordSize(usize),
+    /// Changes the number of bytes per line
+    Cols(usize),
+    /// Changes what `]` and `[` stop at
+    Stops(Kinds),
+    /// Saves what's on screen to a file
+    Screenshot(PathBuf),
+    /// Saves the scope, or all of the data, to a file as base64
+    Base64(PathBuf),
+    /// Stops highlighting the matches of the last search
+    NoHighlight,
+    /// Limits searches and scans to part of the data, or lifts the limit
+    Scope(Option<Range<usize>>),
+    /// Shows the data from the cursor as a picture this many pixels wide
+    Bitmap {
+        width: usize,
+        depth: Depth,
+    },
+    /// Compares the SHA-256 of the data with a digest
+    Verify([u8; 32]),
+    /// Decodes the bytes at the cursor as every kind of timestamp
+    Time {
+        big_endian: bool,
+    },
+}
+
+/// Every command as the names it goes by, its argument and what it does, for
+/// the help screen
+pub const COMMANDS: &[(&[&str], &str, &str)] = &[
+    (
+        &["goto"],
+        "OFFSET",
+        "move the cursor to OFFSET, in decimal or 0x hex",
+    ),
+    (&["record"], "N", "move the cursor to the start of record N"),
+    (
+        &["recsize"],
+        "BYTES",
+        "count records of BYTES each, 0 for none",
+    ),
+    (&["cols"], "N", "show N bytes per line"),
+    (
+        &["stops"],
+        "KINDS",
+        "make ] and [ stop at matches, changes or all",
+    ),
+    (
+        &["scope"],
+        "[START..END]",
+        "search and scan only from START up to END, or everything again",
+    ),
+    (
+        &["time"],
+        "[be]",
+        "read the bytes at the cursor as timestamps, big endian with be",
+    ),
+    (
+        &["bitmap"],
+        "WIDTH [grey]",
+        "draw the data as a picture WIDTH pixels wide, a bit a pixel or a byte with grey",
+    ),
+    (
+        &["verify"],
+        "sha256 DIGEST",
+        "check the data against a SHA-256 digest in hex",
+    ),
+    (
+        &["screenshot"],
+        "PATH",
+        "save the screen to PATH, as SVG for .svg and colored text otherwise",
+    ),
+    (
+        &["base64"],
+        "PATH",
+        "save the scope, or all of the data, to PATH as base64",
+    ),
+    (&["h", "help"], "", "show the help"),
+    (&["mes", "messages"], "", "show the recent messages again"),
+    (
+        &["noh", "nohl", "nohlsearch"],
+        "",
+        "stop highlighting the matches of the last search",
+    ),
+    (&["q", "quit"], "", "quit"),
+];
+
+/// Parses what was typed after the `:`
+pub fn parse_command(text: &str) -> Result<Command> {
+    let mut words = text.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let args: Vec<&str> = words.collect();
+    // :verify takes the algorithm and the digest and :bitmap the width and
+    // depth, everything else one at most
+    let max_args = if matches!(name, "verify" | "bitmap") {
+        2
+    } else {
+        1
+    };
+    if args.len() > max_args {
+        return Err(Error::Parse(format!("too many arguments to :{name}")));
+    }
+    let arg = args.first().copied();
+    let number = |what: &str| {
+        let text = arg.ok_or_else(|| Error::Parse(format!(":{name} needs {what}")))?;
+        parse_number(text)
+    };
+    let command = match name {
+        "q" | "quit" => Command::Quit,
+        "h" | "help" => Command::Help,
+        "mes" | "messages" => Command::Messages,
+        "noh" | "nohl" | "nohlsearch" => Command::NoHighlight,
+        "goto" => Command::Goto(number("an offset")?),
+        "record" => Command::Record(number("a record number")?),
+        "recsize" => Command::RecordSize(number("a record size")?),
+        "cols" => match number("a line length")? {
+            cols @ 1..=MAX_LINE_LENGTH => Command::Cols(cols),
+            _ => {
+                return Err(Error::Parse(format!(
+                    "line length must be from 1 to {MAX_LINE_LENGTH}"
+                )))
+            }
+        },
+        "stops" => {
+            let kinds =
+                arg.ok_or_else(|| Error::Parse(format!(":stops needs {}", Kinds::NAMES)))?;
+            Command::Stops(Kinds::parse(kinds)?)
+        }
+        "scope" => Command::Scope(arg.map(parse_range).transpose()?),
+        "time" => match arg {
+            None | Some("le") => Command::Time { big_endian: false },
+            Some("be") => Command::Time { big_endian: true },
+            Some(other) => {
+                return Err(Error::Parse(format!(":time takes le or be, not '{other}'")))
+            }
+        },
+        "bitmap" => {
+            let width = match number("a width in pixels")? {
+                width @ 1..=MAX_BITMAP_WIDTH => width,
+                _ => {
+                    return Err(Error::Parse(format!(
+                        "a bitmap is from 1 to {MAX_BITMAP_WIDTH} pixels wide"
+                    )))
+                }
+            };
+            let depth = match args.get(1) {
+                None => Depth::Bit,
+                Some(&"grey" | &"gray") => Depth::Byte,
+                Some(other) => {
+                    return Err(Error::Parse(format!(
+                        ":bitmap takes grey after the width, not '{other}'"
+                    )))
+                }
+            };
+            Command::Bitmap { width, depth }
+        }
+        "verify" => match args[..] {
+            ["sha256", digest] => Command::Verify(parse_digest(digest)?),
+            [algorithm, _] => {
+                return Err(Error::Parse(format!(
+                    ":verify only knows sha256, not '{algorithm}'"
+                )))
+            }
+            _ => {
+                return Err(Error::Parse(
+                    ":verify needs sha256 and a digest".to_string(),
+                ))
+            }
+        },
+        "screenshot" => {
+            let path = arg.ok_or_else(|| Error::Parse(":screenshot needs a path".to_string()))?;
+            Command::Screenshot(PathBuf::from(path))
+        }
+        "base64" => {
+            let path = arg.ok_or_else(|| Error::Parse(":base64 needs a path".to_string()))?;
+            Command::Base64(PathBuf::from(path))
+        }
+        "w" | "write" | "fill" | "from-base64" => {
+            return Err(Error::Parse(format!(
+                ":{name} needs editing, which isn't supported"
+            )))
+        }
+        "" => return Err(Error::Parse("no command".to_string())),
+        _ => return Err(Error::Parse(format!("unknown command :{name}"))),
+    };
+    let no_arguments = matches!(
+        command,
+        Command::Quit | Command::Help | Command::Messages | Command::NoHighlight
+    );
+    if arg.is_some() && no_arguments {
+        return Err(Error::Parse(format!(":{name} takes no arguments")));
+    }
+    Ok(command)
+}
+
+/// Parses `START..END`, which must have at least one byte in it
+fn parse_range(text: &str) -> Result<Range<usize>> {
+    let (start, end) = text
+        .split_once("..")
+        .ok_or_else(|| Error::Parse(format!("a range is START..END, not '{text}'")))?;
+    let range = parse_number(start)?..parse_number(end)?;
+    if range.is_empty() {
+        return Err(Error::Parse(format!("{text} is empty")));
+    }
+    Ok(range)
+}
+
+/// Parses a SHA-256 digest written in hex, like `sha256sum` prints it
+fn parse_digest(text: &str) -> Result<[u8; 32]> {
+    let error = || Error::Parse(format!("a SHA-256 digest is 64 hex digits, not '{text}'"));
+    let nibbles: Vec<u8> = text
+        .chars()
+        .map(|c| c.to_digit(16).map(|nibble| nibble as u8))
+        .collect::<Option<_>>()
+        .ok_or_else(error)?;
+    if nibbles.len() != 64 {
+        return Err(error());
+    }
+    let mut digest = [0; 32];
+    for (byte, pair) in digest.iter_mut().zip(nibbles.chunks(2)) {
+        *byte = pair[0] << 4 | pair[1];
+    }
+    Ok(digest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse_command("q").unwrap(), Command::Quit);
+        assert_eq!(parse_command(" quit ").unwrap(), Command::Quit);
+        assert_eq!(parse_command("help").unwrap(), Command::Help);
+        assert_eq!(parse_command("mes").unwrap(), Command::Messages);
+        assert_eq!(parse_command("nohl").unwrap(), Command::NoHighlight);
+        assert_eq!(parse_command("goto 0x100").unwrap(), Command::Goto(256));
+        assert_eq!(parse_command("goto 42").unwrap(), Command::Goto(42));
+        assert_eq!(parse_command("record 3").unwrap(), Command::Record(3));
+        assert_eq!(
+            parse_command("recsize 0x20").unwrap(),
+            Command::RecordSize(32)
+        );
+        assert_eq!(
+            parse_command("stops all").unwrap(),
+            Command::Stops(Kinds::default())
+        );
+        assert_eq!(parse_command("cols 32").unwrap(), Command::Cols(32));
+        assert_eq!(
+            parse_command("scope 0x100..0x200").unwrap(),
+            Command::Scope(Some(0x100..0x200))
+        );
+        assert_eq!(parse_command("scope").unwrap(), Command::Scope(None));
+        assert_eq!(
+            parse_command("bitmap 64").unwrap(),
+            Command::Bitmap {
+                width: 64,
+                depth: Depth::Bit
+            }
+        );
+        assert_eq!(
+            parse_command("bitmap 0x20 gray").unwrap(),
+            Command::Bitmap {
+                width: 32,
+                depth: Depth::Byte
+            }
+        );
+        let digest = "BA7816BF8F01CFEA414140DE5DAE2223b00361a396177a9cb410ff61f20015ad";
+        let Command::Verify(parsed) = parse_command(&format!("verify sha256 {digest}")).unwrap()
+        else {
+            panic!("not :verify");
+        };
+        assert_eq!(parsed[..3], [0xba, 0x78, 0x16]);
+        assert_eq!(parsed[31], 0xad);
+        assert_eq!(
+            parse_command("time").unwrap(),
+            Command::Time { big_endian: false }
+        );
+        assert_eq!(
+            parse_command("time be").unwrap(),
+            Command::Time { big_endian: true }
+        );
+        assert_eq!(
+            parse_command("screenshot shot.svg").unwrap(),
+            Command::Screenshot(PathBuf::from("shot.svg"))
+        );
+        assert_eq!(
+            parse_command("base64 out.b64").unwrap(),
+            Command::Base64(PathBuf::from("out.b64"))
+        );
+    }
+
+    #[test]
+    fn every_listed_command_parses() {
+        for (names, arg, _) in COMMANDS {
+            for name in *names {
+                let text = match *arg {
+                    "" => name.to_string(),
+                    "KINDS" => format!("{name} all"),
+                    "[be]" => format!("{name} be"),
+                    "[START..END]" => format!("{name} 0..16"),
+                    "WIDTH [grey]" => format!("{name} 16 grey"),
+                    "sha256 DIGEST" => format!("{name} sha256 {}", "0".repeat(64)),
+                    _ => format!("{name} 16"),
+                };
+                assert!(parse_command(&text).is_ok(), "{text}");
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        for bad in [
+            "",
+            "x",
+            "goto",
+            "goto zz",
+            "goto 1 2",
+            "cols 0",
+            "cols 257",
+            "record",
+            "screenshot",
+            "base64",
+            "from-base64 AAEC",
+            "time 64",
+            "scope 10",
+            "scope 8..8",
+            "scope 1..x",
+            "bitmap",
+            "bitmap 0",
+            "bitmap 4097",
+            "bitmap 0xffffffffffffffff",
+            "bitmap 8 rgb",
+            "bitmap 8 grey 1",
+            "verify",
+            "verify sha256",
+            "verify md5 00",
+            "verify sha256 abc",
+            "verify sha256 +0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0+0",
+            "goto 1 2",
+            "q now",
+            "w",
+            "fill 00",
+        ] {
+            assert!(parse_command(bad).is_err(), "{bad:?}");
+        }
+    }
+}
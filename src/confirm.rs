@@ -0,0 +1,141 @@
+//! Questions asked on the status line, answered with a single key.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// One possible answer, picked by pressing `key`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Choice {
+    pub label: &'static str,
+    pub key: char,
+}
+
+pub const YES_NO: [Choice; 2] = [
+    Choice {
+        label: "yes",
+        key: 'y',
+    },
+    Choice {
+        label: "no",
+        key: 'n',
+    },
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Question {
+    pub text: String,
+    pub choices: Vec<Choice>,
+    /// Index of the choice Enter picks
+    pub default: usize,
+}
+
+impl Question {
+    pub fn yes_no(text: impl Into<String>, default_yes: bool) -> Self {
+        Question::new(text, YES_NO.to_vec(), if default_yes { 0 } else { 1 })
+    }
+
+    pub fn new(text: impl Into<String>, choices: Vec<Choice>, default: usize) -> Self {
+        assert!(
+            default < choices.len(),
+            "default must be one of the choices"
+        );
+        Question {
+            text: text.into(),
+            choices,
+            default,
+        }
+    }
+
+    /// What pressing `key` answers: `Some(Some(i))` for choice `i`,
+    /// `Some(None)` for cancelling with Esc or Ctrl-C, `None` for keys that
+    /// don't answer anything
+    pub fn answer(&self, key: &KeyEvent) -> Option<Option<usize>> {
+        match key.code {
+            KeyCode::Esc => Some(None),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(None),
+            KeyCode::Enter => Some(Some(self.default)),
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.choices
+                    .iter()
+                    .position(|choice| choice.key.eq_ignore_ascii_case(&c))
+                    .map(Some)
+            }
+            _ => None,
+        }
+    }
+
+    /// The question and its choices as shown on the status line, like
+    /// `Overwrite out.svg? (Y)es/(n)o` with the default in capitals
+    pub fn prompt(&self) -> String {
+        let choices: Vec<String> = self
+            .choices
+            .iter()
+            .enumerate()
+            .map(|(i, choice)| {
+                let key = if i == self.default {
+                    choice.key.to_ascii_uppercase()
+                } else {
+                    choice.key
+                };
+                match choice.label.strip_prefix(choice.key) {
+                    Some(rest) => format!("({key}){rest}"),
+                    None => format!("{} ({key})", choice.label),
+                }
+            })
+            .collect();
+        format!("{} {}", self.text, choices.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn prompts() {
+        assert_eq!(
+            Question::yes_no("Quit?", false).prompt(),
+            "Quit? (y)es/(N)o"
+        );
+        let choices = vec![
+            Choice {
+                label: "overwrite",
+                key: 'o',
+            },
+            Choice {
+                label: "keep both",
+                key: 'b',
+            },
+            Choice {
+                label: "cancel",
+                key: 'c',
+            },
+        ];
+        let question = Question::new("out.svg exists.", choices, 0);
+        assert_eq!(
+            question.prompt(),
+            "out.svg exists. (O)verwrite/keep both (b)/(c)ancel"
+        );
+        assert_eq!(question.answer(&key(KeyCode::Char('b'))), Some(Some(1)));
+        assert_eq!(question.answer(&key(KeyCode::Char('C'))), Some(Some(2)));
+    }
+
+    #[test]
+    fn answers() {
+        let question = Question::yes_no("Quit?", true);
+        assert_eq!(question.answer(&key(KeyCode::Char('n'))), Some(Some(1)));
+        assert_eq!(question.answer(&key(KeyCode::Enter)), Some(Some(0)));
+        assert_eq!(question.answer(&key(KeyCode::Esc)), Some(None));
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(question.answer(&ctrl_c), Some(None));
+        assert_eq!(question.answer(&key(KeyCode::Char('j'))), None);
+        assert_eq!(question.answer(&key(KeyCode::Down)), None);
+    }
+}
@@ -0,0 +1,2023 @@
+use std::fmt::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{
+    Event, KeyCode, KeyCode::Char, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+};
+use crossterm::style::Color;
+
+use crate::backend::{Backend, EventSource, MemoryBackend, Style};
+use crate::base64;
+use crate::bitmap::BitmapView;
+use crate::command::{self, Command};
+use crate::confirm::Question;
+use crate::digest;
+use crate::fields::{self, FieldSpec, Kind};
+use crate::fold::{Folds, MIN_FOLD};
+use crate::heat::Histogram;
+use crate::help::{self, HelpStep, HelpView};
+use crate::history::{Histories, History};
+use crate::interest::{Kinds, Sources};
+use crate::keymap::{self, Action};
+use crate::layout::{Layout, OFFSET_WIDTH};
+use crate::line::LineEdit;
+use crate::message::{Messages, Severity};
+use crate::printable::Printable;
+use crate::screenshot;
+use crate::search;
+use crate::status::{self, StatusFormat, Values};
+use crate::strings::{self, Candidate};
+use crate::viewport;
+
+pub const LINE_LENGTH: usize = 16;
+
+// How long to wait for input before checking for termination signals and
+// expired status messages
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Smallest terminal the hex view is drawn on, anything smaller just gets a
+/// note asking for more room
+pub const MIN_COLUMNS: u16 = 20;
+pub const MIN_ROWS: u16 = 3;
+
+/// Default distance between the offsets highlighted by the ruler
+pub const RULER_INTERVAL: usize = 0x100;
+
+const RULER_STYLE: Style = Style {
+    fg: Some(Color::DarkCyan),
+    bg: None,
+};
+
+/// How search matches look
+const HIGHLIGHT_STYLE: Style = Style {
+    fg: Some(Color::Black),
+    bg: Some(Color::Yellow),
+};
+
+/// How the other matches of the last search look
+const MARK_STYLE: Style = Style {
+    fg: Some(Color::Black),
+    bg: Some(Color::DarkYellow),
+};
+
+/// How the summary of a folded run looks
+const FOLD_STYLE: Style = Style {
+    fg: Some(Color::DarkGrey),
+    bg: None,
+};
+
+/// Most bytes `e` decodes as a string
+pub const MAX_STRING_LEN: usize = 64;
+
+/// How long a status message stays up before the default line comes back
+pub const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// The half of the screen the cursor is in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pane {
+    Hex,
+    Ascii,
+}
+
+/// What the mouse wheel does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wheel {
+    /// Scrolls the view by this many lines like a pager, leaving the cursor
+    /// where it is even if that's off screen
+    Scroll(usize),
+    /// Moves the cursor a line, like `j` and `k`
+    MoveCursor,
+}
+
+impl Default for Wheel {
+    fn default() -> Self {
+        Wheel::Scroll(3)
+    }
+}
+
+/// What's left to do after applying an event
+enum Step {
+    Continue { redraw: bool },
+    Stop(Outcome),
+}
+
+/// What the caller of `tick` should do next
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Continue,
+    Quit,
+    /// The user asked to be dropped back into the shell
+    Suspend,
+}
+
+pub struct Editor {
+    content: Vec<u8>,
+    start: usize,
+    total_lines: usize,
+    layout: Layout,
+    /// Offset of the byte under the cursor
+    cursor: usize,
+    /// Which nibble of the byte the cursor is on in the hex pane, 0 or 1
+    nibble: usize,
+    pane: Pane,
+    /// First byte of each line drawn when the screen is too narrow for all of
+    /// them, see `Layout::fit`
+    first: usize,
+    /// Index within the line that vertical movement tries to keep, so passing
+    /// over a short last line doesn't lose the column. `None` until the first
+    /// vertical move after the cursor was put somewhere else.
+    column: Option<usize>,
+    /// Count typed so far for the next command
+    count: Option<usize>,
+    /// Messages shown instead of the default status line
+    messages: Messages,
+    /// Byte frequencies of `content` while the heat map is shown
+    heat: Option<Histogram>,
+    /// Struct layout `S` decodes the bytes at the cursor as
+    fields: Vec<FieldSpec>,
+    printable: Printable,
+    /// Offset `e` last decoded a string at, and the candidate it showed
+    encoding: Option<(usize, usize)>,
+    wheel: Wheel,
+    /// Earlier version of the data, bytes that differ from it are tinted
+    baseline: Option<Vec<u8>>,
+    /// Lines with a multiple of this offset on them get their offset
+    /// highlighted, 0 for none
+    ruler: usize,
+    /// The `:` command line or the `/` search prompt while it's open
+    prompt: Option<Prompt>,
+    /// Bytes of the match the search prompt is showing
+    found: Option<Range<usize>>,
+    /// What was entered on each prompt before
+    history: Histories,
+    /// Bytes the last search accepted with Enter looked for
+    last_search: Option<Vec<u8>>,
+    /// Whether every match of `last_search` on screen is highlighted, until
+    /// `:nohl`
+    mark_matches: bool,
+    /// Part of the data searches and scans are limited to by `:scope`
+    scope: Option<Range<usize>>,
+    /// The open `:bitmap` screen, drawn instead of the data
+    bitmap: Option<BitmapView>,
+    /// What `]` and `[` stop at
+    stops: Kinds,
+    /// What the status line shows when there's nothing else to show, instead
+    /// of the hint
+    status_format: Option<StatusFormat>,
+    /// Name of the file for the status line
+    file_name: String,
+    /// Size of the records `:record` counts in, 0 for none
+    record_size: usize,
+    /// Whether the status line shows the byte under the cursor in every base
+    byte_readout: bool,
+    /// The help screen while it's open
+    help: Option<HelpView>,
+    /// Runs of a repeated byte shown as a single row. `start` and every other
+    /// row count folds as one row.
+    folds: Folds,
+    /// Question waiting for an answer, and what to do with it
+    question: Option<(Question, OnAnswer)>,
+}
+
+/// Called with the index of the choice picked for a question, or `None` if
+/// it was cancelled, along with whatever the asker captured. Returns how the
+/// editor should stop if it should.
+pub type OnAnswer = Box<dyn FnOnce(&mut Editor, Option<usize>) -> Option<Outcome>>;
+
+/// What has been typed on the command line so far
+struct Prompt {
+    kind: PromptKind,
+    line: LineEdit,
+    /// Why the last command entered didn't run, or why the search found
+    /// nothing
+    error: Option<String>,
+    /// How far back in the history Up and Down have gone, `None` while the
+    /// text is what was typed
+    recall: Option<usize>,
+    /// Text typed before stepping through the history. Only entries that
+    /// contain it are recalled.
+    draft: String,
+}
+
+enum PromptKind {
+    Command,
+    Search {
+        /// Where the search started from, and where Esc goes back to
+        origin: Position,
+        /// Whether the text changed since the last search
+        stale: bool,
+    },
+}
+
+impl Prompt {
+    fn new(kind: PromptKind) -> Self {
+        Prompt {
+            kind,
+            line: LineEdit::default(),
+            error: None,
+            recall: None,
+            draft: String::new(),
+        }
+    }
+
+    fn history<'a>(&self, histories: &'a mut Histories) -> &'a mut History {
+        match self.kind {
+            PromptKind::Command => &mut histories.command,
+            PromptKind::Search { .. } => &mut histories.search,
+        }
+    }
+
+    /// Replaces the text with an older entry of `history` for Up, or a newer
+    /// one for Down, going back to the typed text after the newest. Returns
+    /// whether the text changed.
+    fn recall(&mut self, history: &History, older: bool) -> bool {
+        if self.recall.is_none() {
+            self.draft = self.line.text().to_string();
+        }
+        let next = match (older, self.recall) {
+            (true, back) => history.older(back, &self.draft),
+            (false, Some(back)) => history.newer(back, &self.draft),
+            (false, None) => return false,
+        };
+        match next {
+            Some(back) => self.line.set_text(history.get(back).unwrap_or_default()),
+            // Nothing older, stay on the oldest
+            None if older => return false,
+            None => self.line.set_text(self.draft.clone()),
+        }
+        self.recall = next;
+        true
+    }
+
+    /// The character the prompt starts with
+    fn leader(&self) -> char {
+        match self.kind {
+            PromptKind::Command => ':',
+            PromptKind::Search { .. } => '/',
+        }
+    }
+}
+
+/// Where the cursor and the view are, so they can be put back exactly
+#[derive(Clone, Copy)]
+struct Position {
+    cursor: usize,
+    nibble: usize,
+    start: usize,
+    first: usize,
+    column: Option<usize>,
+}
+
+/// Formats line `pos` of `blob` into `line`, padded or truncated to exactly
+/// `cols` characters. `line` is reused between calls so drawing a frame
+/// doesn't allocate once the buffer has grown to the screen width.
+fn format_line(
+    line: &mut String,
+    layout: &Layout,
+    printable: Printable,
+    blob: &[u8],
+    pos: usize,
+    cols: usize,
+) {
+    let line_start = pos * layout.line_length;
+    let line_num = line_start + layout.first;
+    let bytes = blob.get(line_num..).unwrap_or_default();
+    let bytes = &bytes[..bytes.len().min(layout.visible)];
+
+    line.clear();
+    // Writing to a String can't fail
+    let _ = write!(line, "{line_num:08x}: ");
+    // Glyphs can take more than a byte, which puts everything after them
+    // that much further into `line` than their column
+    let mut skew = 0;
+    let ascii_left = layout.ascii && layout.ascii_left;
+    if ascii_left {
+        line.extend(bytes.iter().map(|&c| printable.glyph(c)));
+        skew = line.len() - layout.ascii_column(layout.first + bytes.len());
+    }
+    for (i, c) in bytes.iter().enumerate() {
+        pad(line, layout.hex_column(layout.first + i) + skew);
+        if layout.nibbles {
+            let _ = write!(line, "{:x} {:x}", c >> 4, c & 0xf);
+        } else {
+            let _ = write!(line, "{c:02x}");
+        }
+    }
+    // Everything up to here but the glyphs is ASCII, and with them on the
+    // left the whole line fits, so this is a char boundary
+    line.truncate(cols + skew);
+    let mut width = line.len() - skew;
+    // The ASCII pane is only drawn when there's room for all of it
+    if layout.ascii {
+        if !ascii_left {
+            pad(line, layout.ascii_column(layout.first));
+            line.extend(bytes.iter().map(|&c| printable.glyph(c)));
+            width = layout.ascii_column(layout.first) + bytes.len();
+        }
+        if layout.right_offset {
+            // Glyphs can take more than a byte, so `pad` would come up short
+            line.extend(std::iter::repeat_n(
+                ' ',
+                layout.right_offset_column() - width,
+            ));
+            let _ = write!(line, "{line_num:08x}");
+            width = layout.width();
+        }
+    }
+    line.extend(std::iter::repeat_n(' ', cols.saturating_sub(width)));
+}
+
+/// Formats the summary row of a fold over `lines` into `line`, padded or
+/// truncated to exactly `cols` characters like `format_line`
+fn format_fold(
+    line: &mut String,
+    line_length: usize,
+    blob: &[u8],
+    lines: Range<usize>,
+    cols: usize,
+) {
+    let start = lines.start * line_length;
+    line.clear();
+    let _ = write!(
+        line,
+        "{start:08x}: ... {:#x} bytes of {:#04x} ...",
+        lines.len() * line_length,
+        blob[start]
+    );
+    line.truncate(cols);
+    pad(line, cols);
+}
+
+fn too_small(columns: u16, rows: u16) -> bool {
+    columns < MIN_COLUMNS || rows < MIN_ROWS
+}
+
+/// Blanks the screen and centers a note asking for a bigger terminal
+fn draw_too_small(backend: &mut impl Backend, columns: u16, rows: u16) -> std::io::Result<()> {
+    let message = format!("terminal too small (need ≥ {MIN_COLUMNS}x{MIN_ROWS})");
+    let message: String = message.chars().take(columns as usize).collect();
+    let width = message.chars().count() as u16;
+    let blank = " ".repeat(columns as usize);
+    for row in 0..rows {
+        backend.move_to(0, row)?;
+        backend.print(&blank, Style::default())?;
+    }
+    if rows > 0 {
+        backend.move_to((columns - width) / 2, rows / 2)?;
+        backend.print(&message, Style::default())?;
+    }
+    backend.move_to(0, 0)?;
+    backend.flush()
+}
+
+/// Rows of `backend` available for data. Drawing, cursor movement and
+/// scrolling all ask here, so they agree on where the status line starts.
+fn data_rows(backend: &impl Backend) -> std::io::Result<usize> {
+    Ok(viewport::data_rows(backend.size()?.1 as usize))
+}
+
+/// Cuts `line` down to at most `width` bytes, without cutting a character in
+/// half
+fn truncate(line: &mut String, width: usize) {
+    let mut width = width.min(line.len());
+    while !line.is_char_boundary(width) {
+        width -= 1;
+    }
+    line.truncate(width);
+}
+
+/// Columns `c` takes on a terminal: two for the wide characters of the East
+/// Asian scripts and for emoji, one for everything else
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3041..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+/// Cuts `line` down to what fits in `columns` columns of the screen, which
+/// for wide characters is fewer characters than columns. Returns how many
+/// columns are left taken.
+fn truncate_to_width(line: &mut String, columns: usize) -> usize {
+    let mut width = 0;
+    for (i, c) in line.char_indices() {
+        if width + char_width(c) > columns {
+            line.truncate(i);
+            break;
+        }
+        width += char_width(c);
+    }
+    width
+}
+
+fn pad(line: &mut String, width: usize) {
+    let len = line.len();
+    line.extend(std::iter::repeat_n(' ', width.saturating_sub(len)));
+}
+
+impl Editor {
+    pub fn new(content: Vec<u8>) -> Self {
+        let layout = Layout::new(LINE_LENGTH);
+        let total_lines = viewport::total_lines(content.len(), layout.line_length);
+        Editor {
+            content,
+            start: 0,
+            total_lines,
+            layout,
+            cursor: 0,
+            nibble: 0,
+            pane: Pane::Hex,
+            first: 0,
+            column: None,
+            count: None,
+            messages: Messages::default(),
+            heat: None,
+            fields: Vec::new(),
+            printable: Printable::default(),
+            encoding: None,
+            wheel: Wheel::default(),
+            baseline: None,
+            ruler: RULER_INTERVAL,
+            prompt: None,
+            found: None,
+            history: Histories::default(),
+            last_search: None,
+            mark_matches: false,
+            scope: None,
+            bitmap: None,
+            stops: Kinds::default(),
+            status_format: None,
+            file_name: String::new(),
+            record_size: 0,
+            byte_readout: false,
+            help: None,
+            folds: Folds::default(),
+            question: None,
+        }
+    }
+
+    /// Tints every byte that differs from `baseline`, or isn't in it at all
+    pub fn with_baseline(mut self, baseline: Option<Vec<u8>>) -> Self {
+        self.baseline = baseline;
+        self
+    }
+
+    /// Highlights the offset of every line that has a multiple of `interval`
+    /// on it, or none for 0
+    pub fn with_ruler(mut self, interval: usize) -> Self {
+        self.ruler = interval;
+        self
+    }
+
+    pub fn with_wheel(mut self, wheel: Wheel) -> Self {
+        self.wheel = wheel;
+        self
+    }
+
+    /// Sets which bytes the ASCII pane shows as characters
+    pub fn with_printable(mut self, printable: Printable) -> Self {
+        self.printable = printable;
+        self
+    }
+
+    /// Starts the prompts off with what was entered in earlier sessions
+    pub fn with_history(mut self, history: Histories) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// What has been entered on the prompts, including `with_history`
+    pub fn history(&self) -> &Histories {
+        &self.history
+    }
+
+    /// Shows `format` in the status line instead of the hint
+    pub fn with_status_format(mut self, format: Option<StatusFormat>) -> Self {
+        self.status_format = format;
+        self
+    }
+
+    /// Names the file for the status line
+    pub fn with_file_name(mut self, name: impl Into<String>) -> Self {
+        self.file_name = name.into();
+        self
+    }
+
+    /// Draws the ASCII pane before the hex pane instead of after it
+    pub fn with_ascii_left(mut self, ascii_left: bool) -> Self {
+        self.layout.ascii_left = ascii_left;
+        self
+    }
+
+    /// Repeats the offset of each line after the ASCII pane, when there's
+    /// room for it
+    pub fn with_right_offset(mut self, right_offset: bool) -> Self {
+        self.layout.right_offset = right_offset;
+        self
+    }
+
+    /// Counts the data in records of `size` bytes for `:record`, and shows
+    /// which one the cursor is in. 0 for none.
+    pub fn with_record_size(mut self, size: usize) -> Self {
+        self.record_size = size;
+        self
+    }
+
+    /// Shows the byte under the cursor in the corner of the status line, in
+    /// every base
+    pub fn with_byte_readout(mut self, byte_readout: bool) -> Self {
+        self.byte_readout = byte_readout;
+        self
+    }
+
+    /// Sets the struct layout that `S` decodes at the cursor
+    pub fn with_fields(mut self, fields: Vec<FieldSpec>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// Index of the first row shown on screen, which is the first line shown
+    /// unless something above it is folded
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    /// Offset of the byte under the cursor
+    pub fn offset(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn pane(&self) -> Pane {
+        self.pane
+    }
+
+    /// Waits for the next event and reacts to it, updating the screen as needed
+    pub fn tick(
+        &mut self,
+        backend: &mut impl Backend,
+        events: &mut impl EventSource,
+    ) -> std::io::Result<Outcome> {
+        let Some(mut event) = events.next_event(POLL_INTERVAL)? else {
+            self.idle(backend, Instant::now())?;
+            return Ok(Outcome::Continue);
+        };
+        // Events can arrive faster than the screen redraws, e.g. while j is
+        // held down. Apply everything that's already queued and redraw once,
+        // so the view stops as soon as the key is let go.
+        let mut requires_redraw = false;
+        loop {
+            match self.apply_event(backend, event)? {
+                Step::Continue { redraw } => requires_redraw |= redraw,
+                Step::Stop(outcome) => {
+                    // Leave the screen matching whatever came before
+                    self.render(backend, requires_redraw)?;
+                    return Ok(outcome);
+                }
+            }
+            match events.next_event(Duration::ZERO)? {
+                Some(next) => event = next,
+                None => break,
+            }
+        }
+        requires_redraw |= self.update_search(backend)?;
+        self.render(backend, requires_redraw)?;
+        Ok(Outcome::Continue)
+    }
+
+    /// Asks `question` on the status line, blocking every other key and the
+    /// mouse until it's answered. `on_answer` then gets the choice.
+    pub fn ask(
+        &mut self,
+        question: Question,
+        on_answer: impl FnOnce(&mut Editor, Option<usize>) -> Option<Outcome> + 'static,
+    ) {
+        self.question = Some((question, Box::new(on_answer)));
+    }
+
+    fn answer(&mut self, choice: Option<usize>) -> Step {
+        let Some((_, on_answer)) = self.question.take() else {
+            return Step::Continue { redraw: false };
+        };
+        match on_answer(self, choice) {
+            Some(outcome) => Step::Stop(outcome),
+            // Whatever the answer did, the status line needs redrawing
+            None => Step::Continue { redraw: true },
+        }
+    }
+
+    /// Shows `message` in the status line until it times out or a key is
+    /// pressed, after any messages that are already up
+    pub fn post(&mut self, severity: Severity, message: impl Into<String>) {
+        self.messages.post(severity, message.into(), Instant::now());
+    }
+
+    /// The message in the status line, if there is one
+    pub fn status(&self) -> Option<&str> {
+        self.messages.current().map(|(message, _)| message)
+    }
+
+    /// Housekeeping for when no input arrived, `now` being the current time.
+    /// Moves on from the status message once it has been up for
+    /// `STATUS_TIMEOUT`.
+    pub fn idle(&mut self, backend: &mut impl Backend, now: Instant) -> std::io::Result<()> {
+        if self.messages.expire(now, STATUS_TIMEOUT) {
+            let (columns, rows) = backend.size()?;
+            if too_small(columns, rows) {
+                return Ok(());
+            }
+            let data_rows = data_rows(backend)?;
+            self.draw_status(backend, &mut String::new(), columns as usize, data_rows)?;
+            let (col, row) = self.cursor_position(columns, data_rows);
+            backend.move_to(col, row)?;
+            backend.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Reacts to a single event and updates the screen
+    pub fn handle_event(
+        &mut self,
+        backend: &mut impl Backend,
+        event: Event,
+    ) -> std::io::Result<Outcome> {
+        match self.apply_event(backend, event)? {
+            Step::Continue { redraw } => {
+                let redraw = self.update_search(backend)? || redraw;
+                self.render(backend, redraw)?;
+                Ok(Outcome::Continue)
+            }
+            Step::Stop(outcome) => Ok(outcome),
+        }
+    }
+
+    /// Updates the editor for `event`, leaving most of the drawing to `render`
+    fn apply_event(&mut self, backend: &mut impl Backend, event: Event) -> std::io::Result<Step> {
+        log::debug!("event {event:?}");
+        // Some platforms (Windows) also report releases, which would make
+        // every key count twice. Repeats are presses as far as we care.
+        if let Event::Key(KeyEvent {
+            kind: KeyEventKind::Release,
+            ..
+        }) = event
+        {
+            return Ok(Step::Continue { redraw: false });
+        }
+        if let Some((question, _)) = &self.question {
+            match &event {
+                Event::Key(key) => {
+                    return Ok(match question.answer(key) {
+                        Some(choice) => self.answer(choice),
+                        None => Step::Continue { redraw: false },
+                    })
+                }
+                // The screen still follows the terminal's size
+                Event::Resize(_, _) => {}
+                // Nothing else happens until the question is answered
+                _ => return Ok(Step::Continue { redraw: false }),
+            }
+        }
+        // Messages are about what happened before the key was pressed
+        let cleared = matches!(event, Event::Key(_)) && self.messages.clear();
+        let old_count = self.count;
+        if self.help.is_some() || self.bitmap.is_some() {
+            if let Some(step) = self.view_event(backend, &event)? {
+                return Ok(step);
+            }
+        }
+        if let (Event::Key(key), true) = (&event, self.prompt.is_some()) {
+            // Keys are typed into the prompt, none of the bindings apply
+            return self.prompt_key(backend, *key);
+        }
+        if let Event::Key(key) = event {
+            match keymap::action_for(&key) {
+                Some(Action::Quit) => return Ok(Step::Stop(Outcome::Quit)),
+                #[cfg(unix)]
+                Some(Action::Suspend) => return Ok(Step::Stop(Outcome::Suspend)),
+                _ => {}
+            }
+        }
+
+        let (columns, rows) = backend.size()?;
+        if too_small(columns, rows) {
+            // Nothing but the size warning is on screen, wait for a usable size
+            let redraw = matches!(event, Event::Resize(_, _));
+            return Ok(Step::Continue { redraw });
+        }
+
+        let requires_redraw = match event {
+            Event::Key(key) => match (key.code, self.count.take()) {
+                // A count is typed before the command, like in vim. 0 only
+                // continues one, on its own it goes to the start of the line.
+                (Char(c @ '0'..='9'), count) if c != '0' || count.is_some() => {
+                    let digit = c as usize - '0' as usize;
+                    self.count = Some(count.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+                    false
+                }
+                (_, count) => match keymap::action_for(&key) {
+                    Some(action) => self.perform(backend, action, count)?,
+                    None => false,
+                },
+            },
+            Event::Mouse(e) => match e.kind {
+                MouseEventKind::ScrollUp => self.wheel(backend, Direction::Up)?,
+                MouseEventKind::ScrollDown => self.wheel(backend, Direction::Down)?,
+                MouseEventKind::Down(MouseButton::Left) => {
+                    self.click(backend, e)?;
+                    false
+                }
+                _ => false,
+            },
+            Event::Resize(_, _) => {
+                let data_rows = data_rows(backend)?;
+                self.start = viewport::clamp_start(self.start, self.total_rows(), data_rows);
+                self.scroll_to_cursor(data_rows);
+                true
+            }
+            _ => false,
+        };
+        let requires_redraw = self.scroll_horizontally(backend)? || requires_redraw;
+        // The status line shows the count while it's being typed, and the
+        // format, the byte and the record can show anything about the cursor
+        let status_changed = cleared
+            || self.count != old_count
+            || self.status_format.is_some()
+            || self.byte_readout
+            || self.record_size > 0;
+        if status_changed && !requires_redraw {
+            self.redraw_status(backend)?;
+        }
+
+        log::trace!(
+            "start {} cursor {:#x}.{} redraw {requires_redraw}",
+            self.start,
+            self.cursor,
+            self.nibble
+        );
+        Ok(Step::Continue {
+            redraw: requires_redraw,
+        })
+    }
+
+    /// Brings the screen up to date after handling events, redrawing all of
+    /// it only if `redraw` is set
+    fn render(&self, backend: &mut impl Backend, redraw: bool) -> std::io::Result<()> {
+        if redraw {
+            return self.draw(backend);
+        }
+        let (columns, rows) = backend.size()?;
+        if too_small(columns, rows) {
+            return Ok(());
+        }
+        let (col, row) = self.cursor_position(columns, data_rows(backend)?);
+        backend.move_to(col, row)?;
+        backend.flush()
+    }
+
+    /// Does what `action` is bound to, with the count typed before it. Returns
+    /// whether the screen needs a redraw.
+    fn perform(
+        &mut self,
+        backend: &mut impl Backend,
+        action: Action,
+        count: Option<usize>,
+    ) -> std::io::Result<bool> {
+        let line_length = self.layout.line_length;
+        Ok(match action {
+            // Handled before anything else
+            Action::Quit | Action::Suspend => false,
+            Action::Left => self.move_cursor(backend, Direction::Left, count)?,
+            Action::Down => self.move_cursor(backend, Direction::Down, count)?,
+            Action::Up => self.move_cursor(backend, Direction::Up, count)?,
+            Action::Right => self.move_cursor(backend, Direction::Right, count)?,
+            Action::LineStart => {
+                self.set_cursor(self.cursor - self.cursor % line_length);
+                self.nibble = 0;
+                false
+            }
+            Action::LineEnd => {
+                if let Some(last) = self.last_byte_of_line(self.cursor / line_length) {
+                    self.set_cursor(last);
+                    self.nibble = self.last_nibble();
+                    // Like vim, keep to the end of lines when moving on
+                    self.column = Some(line_length - 1);
+                }
+                false
+            }
+            Action::Top => self.goto_start(),
+            Action::Bottom => match count {
+                Some(line) => self.goto_line(backend, line)?,
+                None => self.goto_end(backend)?,
+            },
+            Action::NextInterest => self.goto_interest(backend, true)?,
+            Action::PreviousInterest => self.goto_interest(backend, false)?,
+            Action::SwitchPane => {
+                self.pane = match self.pane {
+                    Pane::Hex => Pane::Ascii,
+                    Pane::Ascii => Pane::Hex,
+                };
+                false
+            }
+            Action::ToggleHeat => {
+                self.toggle_heat();
+                true
+            }
+            Action::DecodeFields => {
+                self.decode_fields();
+                self.redraw_status(backend)?;
+                false
+            }
+            Action::NextEncoding => {
+                self.next_encoding();
+                self.redraw_status(backend)?;
+                false
+            }
+            Action::CommandLine => {
+                self.prompt = Some(Prompt::new(PromptKind::Command));
+                self.redraw_status(backend)?;
+                false
+            }
+            Action::Search => {
+                let origin = self.position();
+                let stale = false;
+                self.prompt = Some(Prompt::new(PromptKind::Search { origin, stale }));
+                self.redraw_status(backend)?;
+                false
+            }
+            Action::ToggleNibbles => {
+                self.layout.nibbles = !self.layout.nibbles;
+                true
+            }
+            Action::Narrower | Action::Wider => {
+                let by = count.unwrap_or(1);
+                let line_length = match action {
+                    Action::Narrower => line_length.saturating_sub(by),
+                    _ => line_length.saturating_add(by),
+                };
+                let line_length = line_length.clamp(1, command::MAX_LINE_LENGTH);
+                let data_rows = data_rows(backend)?;
+                self.set_line_length(line_length, data_rows);
+                self.scroll_to_cursor(data_rows);
+                let bytes = if line_length == 1 { "byte" } else { "bytes" };
+                self.post(Severity::Info, format!("{line_length} {bytes} per line"));
+                true
+            }
+            Action::ToggleFolds => {
+                self.toggle_folds(backend)?;
+                true
+            }
+            Action::OpenFold => {
+                let opened = self.folds.open(self.cursor / line_length);
+                if !opened {
+                    self.post(Severity::Info, "no fold at the cursor");
+                    self.redraw_status(backend)?;
+                }
+                opened
+            }
+            Action::Help => {
+                self.help = Some(HelpView::default());
+                true
+            }
+        })
+    }
+
+    /// Passes keys and the wheel on to the help or bitmap screen, which
+    /// close on any key they don't use. Returns `None` for events that
+    /// aren't about them, like resizes.
+    fn view_event(
+        &mut self,
+        backend: &mut impl Backend,
+        event: &Event,
+    ) -> std::io::Result<Option<Step>> {
+        let rows = data_rows(backend)?;
+        let scroll = |event: &Event| match event {
+            Event::Mouse(e) if e.kind == MouseEventKind::ScrollDown => Some(1),
+            Event::Mouse(e) if e.kind == MouseEventKind::ScrollUp => Some(-1),
+            _ => None,
+        };
+        let step = match (event, &mut self.help, &mut self.bitmap) {
+            (Event::Key(key), Some(view), _) => view.key(key, rows),
+            (Event::Key(key), None, Some(view)) => view.key(key, rows),
+            (Event::Mouse(_), Some(view), _) => {
+                scroll(event).map_or(HelpStep::Status, |by| view.scroll(by, rows))
+            }
+            (Event::Mouse(_), None, Some(view)) => {
+                scroll(event).map_or(HelpStep::Status, |by| view.scroll(by, rows))
+            }
+            _ => return Ok(None),
+        };
+        let redraw = match step {
+            HelpStep::Close => {
+                // The view underneath never changed, drawing it brings it back
+                if self.help.take().is_none() {
+                    self.bitmap = None;
+                }
+                true
+            }
+            HelpStep::Redraw => true,
+            HelpStep::Status => {
+                let (columns, rows) = backend.size()?;
+                if !too_small(columns, rows) {
+                    self.draw_help_status(backend, columns as usize, data_rows(backend)?)?;
+                }
+                false
+            }
+        };
+        Ok(Some(Step::Continue { redraw }))
+    }
+
+    /// Edits the open prompt with `key`, running the command or accepting
+    /// the search on Enter. A command that doesn't parse leaves the prompt
+    /// open with the error after the text, so it can be fixed.
+    fn prompt_key(&mut self, backend: &mut impl Backend, key: KeyEvent) -> std::io::Result<Step> {
+        let Some(prompt) = &mut self.prompt else {
+            return Ok(Step::Continue { redraw: false });
+        };
+        let mut redraw = false;
+        let mut edited = false;
+        let mut close = false;
+        let mut accept = false;
+        let mut recalled = false;
+        if key.code == KeyCode::Enter {
+            // Commands that fail are kept too, so a typo can be recalled and
+            // fixed
+            prompt.history(&mut self.history).push(prompt.line.text());
+        }
+        match key.code {
+            KeyCode::Esc => close = true,
+            Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => close = true,
+            // Like vim, backspacing over the `:` closes the prompt
+            KeyCode::Backspace if prompt.line.text().is_empty() => close = true,
+            KeyCode::Up | KeyCode::Down => {
+                let history = prompt.history(&mut self.history);
+                recalled = prompt.recall(history, key.code == KeyCode::Up);
+            }
+            KeyCode::Enter => match &prompt.kind {
+                PromptKind::Command => match command::parse_command(prompt.line.text()) {
+                    Ok(command) => {
+                        self.prompt = None;
+                        match self.run_command(backend, command)? {
+                            Some(outcome) => return Ok(Step::Stop(outcome)),
+                            None => redraw = true,
+                        }
+                    }
+                    Err(err) => prompt.error = Some(err.to_string()),
+                },
+                PromptKind::Search { .. } => accept = true,
+            },
+            _ => edited = prompt.line.key(&key).unwrap_or(false),
+        }
+        if accept {
+            // Keys typed in the same batch as Enter haven't been searched for
+            self.update_search(backend)?;
+            // The cursor is on the match, or back where it started if there
+            // was none
+            match self.prompt.take() {
+                Some(Prompt {
+                    line,
+                    error: Some(error),
+                    ..
+                }) => self.post(Severity::Warning, format!("{}: {error}", line.text())),
+                Some(Prompt { line, .. }) => {
+                    let needle = search::parse_pattern(line.text(), self.pane).unwrap_or_default();
+                    if !needle.is_empty() {
+                        self.last_search = Some(needle);
+                        self.mark_matches = true;
+                    }
+                }
+                None => {}
+            }
+            self.found = None;
+            redraw = true;
+        }
+        if let Some(prompt) = &mut self.prompt {
+            if edited {
+                prompt.recall = None;
+            }
+            if edited || recalled {
+                prompt.error = None;
+                if let PromptKind::Search { stale, .. } = &mut prompt.kind {
+                    *stale = true;
+                }
+            }
+            if close {
+                if let PromptKind::Search { origin, .. } = prompt.kind {
+                    self.restore(origin);
+                    self.found = None;
+                    redraw = true;
+                }
+                self.prompt = None;
+            }
+        }
+        let (columns, rows) = backend.size()?;
+        if !redraw && !too_small(columns, rows) {
+            self.redraw_status(backend)?;
+        }
+        Ok(Step::Continue { redraw })
+    }
+
+    /// Previews the pattern on the search prompt: moves to its first match
+    /// after where the cursor was when the prompt opened, or back there if
+    /// there's none. Only runs once for all the keys handled since the last
+    /// call, so keys typed faster than the search don't queue up behind it.
+    /// Returns whether the screen needs a redraw.
+    fn update_search(&mut self, backend: &impl Backend) -> std::io::Result<bool> {
+        let Some(Prompt {
+            kind: PromptKind::Search { origin, stale },
+            line,
+            ..
+        }) = &mut self.prompt
+        else {
+            return Ok(false);
+        };
+        if !*stale {
+            return Ok(false);
+        }
+        *stale = false;
+        let origin = *origin;
+        let pattern = search::parse_pattern(line.text(), self.pane);
+        self.restore(origin);
+        self.found = None;
+        let scope = self.scope();
+        let from = self.scope_start_for(true);
+        let error = match pattern {
+            Ok(needle) => match search::find(&self.content[scope.clone()], &needle, from) {
+                Some(offset) => {
+                    let offset = scope.start + offset;
+                    self.set_cursor(offset);
+                    self.nibble = 0;
+                    self.found = Some(offset..offset + needle.len());
+                    None
+                }
+                None if needle.is_empty() => None,
+                None if self.scope.is_some() => Some("not found in the scope".to_string()),
+                None => Some("not found".to_string()),
+            },
+            Err(err) => Some(err.to_string()),
+        };
+        if let Some(prompt) = &mut self.prompt {
+            prompt.error = error;
+        }
+        // With no match the view stays exactly as it was
+        if self.found.is_some() {
+            self.scroll_to_cursor(data_rows(backend)?);
+            self.scroll_horizontally(backend)?;
+        }
+        Ok(true)
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            cursor: self.cursor,
+            nibble: self.nibble,
+            start: self.start,
+            first: self.first,
+            column: self.column,
+        }
+    }
+
+    fn restore(&mut self, position: Position) {
+        self.cursor = position.cursor;
+        self.nibble = position.nibble;
+        self.start = position.start;
+        self.first = position.first;
+        self.column = position.column;
+    }
+
+    /// Runs a command from the command line, returning how the editor should
+    /// stop if it should
+    fn run_command(
+        &mut self,
+        backend: &impl Backend,
+        command: Command,
+    ) -> std::io::Result<Option<Outcome>> {
+        let data_rows = data_rows(backend)?;
+        match command {
+            Command::Quit => return Ok(Some(Outcome::Quit)),
+            Command::Help => self.help = Some(HelpView::default()),
+            Command::Messages => self.show_messages(data_rows),
+            Command::NoHighlight => self.mark_matches = false,
+            Command::Scope(scope) => {
+                match &scope {
+                    Some(range) => self.post(
+                        Severity::Info,
+                        format!("searching only {:#x}..{:#x}", range.start, range.end),
+                    ),
+                    None => self.post(Severity::Info, "searching everything"),
+                }
+                self.scope = scope;
+                if self.heat.is_some() {
+                    let scope = self.scope();
+                    self.heat = Some(Histogram::new(&self.content[scope]));
+                }
+            }
+            Command::Goto(offset) => self.goto(offset),
+            Command::Record(_) if self.record_size == 0 => self.post(
+                Severity::Error,
+                "no record size, set one with :recsize or --record-size",
+            ),
+            Command::Record(record) => self.goto(record.saturating_mul(self.record_size)),
+            Command::RecordSize(size) => {
+                self.record_size = size;
+                match size {
+                    0 => self.post(Severity::Info, "records off"),
+                    _ => self.post(Severity::Info, format!("records of {size} bytes")),
+                }
+            }
+            Command::Stops(kinds) => {
+                self.stops = kinds;
+                self.post(
+                    Severity::Info,
+                    format!("] and [ stop at {}", kinds.describe()),
+                );
+            }
+            Command::Cols(line_length) => self.set_line_length(line_length, data_rows),
+            Command::Screenshot(path) => self.screenshot(backend, path)?,
+            Command::Base64(path) => self.save_base64(path),
+            Command::Time { big_endian } => self.decode_times(big_endian),
+            Command::Bitmap { width, depth } => {
+                let len = self.content.len();
+                self.bitmap = Some(BitmapView::new(width, depth, self.cursor, len));
+            }
+            Command::Verify(expected) => {
+                let digest = digest::sha256(&self.content);
+                if digest == expected {
+                    self.post(Severity::Info, "MATCH, the SHA-256 is the same");
+                } else {
+                    let got = digest::to_hex(&digest);
+                    self.post(Severity::Error, format!("MISMATCH (got {got})"));
+                }
+            }
+        }
+        self.scroll_to_cursor(data_rows);
+        Ok(None)
+    }
+
+    /// Lays the data out in lines of `line_length` bytes, keeping the same
+    /// part of it at the top of the screen. The cursor stays on its byte but
+    /// may need scrolling to.
+    fn set_line_length(&mut self, line_length: usize, data_rows: usize) {
+        let top = self.line_of_row(self.start) * self.layout.line_length;
+        self.layout = Layout {
+            line_length,
+            visible: line_length,
+            ..self.layout
+        };
+        self.total_lines = viewport::total_lines(self.content.len(), line_length);
+        if !self.folds.is_empty() {
+            self.folds = Folds::find(&self.content, line_length, MIN_FOLD);
+        }
+        self.start = self.folds.row_of_line(top / line_length);
+        self.start = viewport::clamp_start(self.start, self.total_rows(), data_rows);
+        self.first = 0;
+        self.column = None;
+    }
+
+    /// Saves what's on screen to `path`, asking first if there's a file
+    /// there already
+    fn screenshot(&mut self, backend: &impl Backend, path: PathBuf) -> std::io::Result<()> {
+        let (columns, rows) = backend.size()?;
+        let mut screen = MemoryBackend::new(columns, rows);
+        self.draw(&mut screen)?;
+        let svg = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+        let text = match svg {
+            true => screenshot::svg(&screen),
+            false => screenshot::ansi(&screen),
+        };
+        let saved = format!("saved the screen to {}", path.display());
+        self.save(path, text, saved);
+        Ok(())
+    }
+
+    /// Saves the scope, or all of the data without one, to `path` as base64
+    /// on a single line
+    fn save_base64(&mut self, path: PathBuf) {
+        let bytes = &self.content[self.scope()];
+        let text = base64::encode(bytes) + "\n";
+        let saved = format!(
+            "saved {} bytes as base64 to {}",
+            bytes.len(),
+            path.display()
+        );
+        self.save(path, text, saved);
+    }
+
+    /// Writes `text` to `path` and posts `saved`, asking first if there's a
+    /// file there already
+    fn save(&mut self, path: PathBuf, text: String, saved: String) {
+        if path.exists() {
+            let question = Question::yes_no(format!("Overwrite {}?", path.display()), false);
+            self.ask(question, move |editor, choice| {
+                match choice {
+                    Some(0) => editor.write_file(&path, &text, saved),
+                    _ => editor.post(Severity::Info, format!("kept {}", path.display())),
+                }
+                None
+            });
+            return;
+        }
+        self.write_file(&path, &text, saved);
+    }
+
+    fn write_file(&mut self, path: &Path, text: &str, saved: String) {
+        match std::fs::write(path, text) {
+            Ok(()) => self.post(Severity::Info, saved),
+            Err(err) => self.post(
+                Severity::Error,
+                format!("couldn't write {}: {err}", path.display()),
+            ),
+        }
+    }
+
+    /// Puts the cursor on `offset`, or the last byte if it's past the end
+    fn goto(&mut self, offset: usize) {
+        self.set_cursor(offset);
+        self.nibble = 0;
+        if offset > self.cursor {
+            self.post(
+                Severity::Warning,
+                format!("{offset:#x} is past the end, went to the last byte"),
+            );
+        }
+    }
+
+    /// Shows the message log the way the help is shown, scrolled to the most
+    /// recent message
+    fn show_messages(&mut self, data_rows: usize) {
+        let lines: Vec<String> = self
+            .messages
+            .log()
+            .map(|(severity, text)| format!("{:<8} {text}", severity.name()))
+            .collect();
+        if lines.is_empty() {
+            self.post(Severity::Info, "no messages yet");
+            return;
+        }
+        let mut view = HelpView::new(lines);
+        view.scroll(isize::MAX, data_rows);
+        self.help = Some(view);
+    }
+
+    /// Shows the bytes at the cursor decoded as the struct layout
+    fn decode_fields(&mut self) {
+        if self.fields.is_empty() {
+            self.post(
+                Severity::Warning,
+                "no struct layout, start with --struct LAYOUT",
+            );
+            return;
+        }
+        let bytes = self.content.get(self.cursor..).unwrap_or_default();
+        let decoded = fields::decode(&self.fields, bytes);
+        self.post(Severity::Info, format!("{:08x}: {decoded}", self.cursor));
+    }
+
+    /// Shows the bytes at the cursor as every kind of timestamp that fits in
+    /// what's left of the data
+    fn decode_times(&mut self, big_endian: bool) {
+        let bytes = self.content.get(self.cursor..).unwrap_or_default();
+        let decoded: Vec<String> = Kind::TIMES
+            .into_iter()
+            .filter(|kind| kind.size() <= bytes.len())
+            .map(|kind| {
+                let field = FieldSpec {
+                    kind,
+                    big_endian,
+                    count: None,
+                };
+                fields::decode(&[field], bytes)
+            })
+            .collect();
+        if decoded.is_empty() {
+            self.post(Severity::Warning, "too few bytes for a timestamp");
+            return;
+        }
+        let decoded = decoded.join(", ");
+        self.post(Severity::Info, format!("{:08x}: {decoded}", self.cursor));
+    }
+
+    /// Shows the next way the text at the cursor could be encoded, starting
+    /// over from the first whenever the cursor has moved
+    fn next_encoding(&mut self) {
+        let candidates =
+            strings::decode_string_candidates(&self.content, self.cursor, MAX_STRING_LEN);
+        if candidates.is_empty() {
+            self.encoding = None;
+            self.post(Severity::Warning, "no text at the cursor");
+            return;
+        }
+        let index = match self.encoding {
+            Some((offset, index)) if offset == self.cursor => (index + 1) % candidates.len(),
+            _ => 0,
+        };
+        self.encoding = Some((self.cursor, index));
+        let Candidate {
+            encoding,
+            text,
+            len,
+        } = &candidates[index];
+        self.post(
+            Severity::Info,
+            format!(
+                "{}: \"{text}\" ({len} bytes) [{}/{}]",
+                encoding.name(),
+                index + 1,
+                candidates.len()
+            ),
+        );
+    }
+
+    /// Shows or hides the byte-frequency heat map
+    fn toggle_heat(&mut self) {
+        if self.heat.take().is_some() {
+            self.post(Severity::Info, "heat map off");
+        } else {
+            self.heat = Some(Histogram::new(&self.content[self.scope()]));
+            self.post(Severity::Info, "heat map on, rarer bytes are brighter");
+        }
+    }
+
+    /// Reacts to the mouse wheel turning `direction`, which is up or down.
+    /// Returns whether the screen needs a redraw.
+    fn wheel(&mut self, backend: &impl Backend, direction: Direction) -> std::io::Result<bool> {
+        let lines = match self.wheel {
+            Wheel::Scroll(lines) => lines,
+            Wheel::MoveCursor => return self.move_cursor(backend, direction, None),
+        };
+        let old_start = self.start;
+        self.start = match direction {
+            Direction::Up => self.start.saturating_sub(lines),
+            _ => viewport::clamp_start(
+                self.start.saturating_add(lines),
+                self.total_rows(),
+                data_rows(backend)?,
+            ),
+        };
+        Ok(self.start != old_start)
+    }
+
+    /// Moves the cursor `count` nibbles (in the hex pane), bytes or lines,
+    /// one if there's no count, scrolling to keep it on screen. Returns
+    /// whether the screen needs a redraw.
+    fn move_cursor(
+        &mut self,
+        backend: &impl Backend,
+        direction: Direction,
+        count: Option<usize>,
+    ) -> std::io::Result<bool> {
+        let data_rows = data_rows(backend)?;
+        let count = count.unwrap_or(1);
+        let row = self.cursor_row();
+
+        match direction {
+            // Vertical movement goes by rows, so a fold is a single step
+            Direction::Up => {
+                if row > 0 {
+                    self.move_to_line(self.line_of_row(row.saturating_sub(count)));
+                }
+            }
+            Direction::Down => {
+                // Moving onto a short last line lands on its last byte
+                if row + 1 < self.total_rows() {
+                    let to = row.saturating_add(count).min(self.total_rows() - 1);
+                    self.move_to_line(self.line_of_row(to));
+                }
+            }
+            // Stops early at either end of the data, so a huge count doesn't
+            // keep stepping in place
+            Direction::Left | Direction::Right => {
+                for _ in 0..count {
+                    if !self.step_sideways(direction) {
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(self.scroll_to_cursor(data_rows))
+    }
+
+    /// Moves the cursor one nibble or byte left or right, wrapping onto the
+    /// neighbouring lines and stepping over a fold as if it was a single
+    /// byte. Returns whether it moved.
+    fn step_sideways(&mut self, direction: Direction) -> bool {
+        let line_length = self.layout.line_length;
+        let fold = self.folds.fold_at(self.cursor / line_length);
+        let last_nibble = self.last_nibble();
+        match direction {
+            Direction::Left => {
+                let from = fold.map_or(self.cursor, |fold| fold.start * line_length);
+                if self.pane == Pane::Hex && self.nibble == 1 {
+                    self.nibble = 0;
+                } else if from > 0 {
+                    let to = match self.folds.fold_at((from - 1) / line_length) {
+                        Some(fold) => fold.start * line_length,
+                        None => from - 1,
+                    };
+                    self.set_cursor(to);
+                    self.nibble = last_nibble;
+                } else {
+                    return false;
+                }
+            }
+            _ => {
+                let to = fold.map_or(self.cursor + 1, |fold| fold.end * line_length);
+                if self.nibble < last_nibble {
+                    self.nibble += 1;
+                } else if to < self.content.len() {
+                    self.set_cursor(to);
+                    self.nibble = 0;
+                } else {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Moves the cursor to `offset`, or to the last byte if that's past the
+    /// end of the data. Every cursor move goes through here, so the cursor
+    /// never addresses a byte that doesn't exist.
+    fn set_cursor(&mut self, offset: usize) {
+        self.cursor = offset.min(self.content.len().saturating_sub(1));
+        self.column = None;
+    }
+
+    /// Moves the cursor to `line`, as close to the remembered column as the
+    /// line allows
+    fn move_to_line(&mut self, line: usize) {
+        let line_length = self.layout.line_length;
+        let column = self.column.unwrap_or(self.cursor % line_length);
+        self.set_cursor(line * line_length + column);
+        self.column = Some(column);
+    }
+
+    /// Last nibble the cursor can be on within a byte
+    fn last_nibble(&self) -> usize {
+        // The ASCII pane has a single cell per byte
+        match self.pane {
+            Pane::Hex => 1,
+            Pane::Ascii => 0,
+        }
+    }
+
+    /// Offset of the last byte that exists on `line`, which is short of a
+    /// full line on the last line of the file
+    fn last_byte_of_line(&self, line: usize) -> Option<usize> {
+        let line_start = line * self.layout.line_length;
+        let line_end = (line_start + self.layout.line_length).min(self.content.len());
+        (line_end > line_start).then(|| line_end - 1)
+    }
+
+    /// Puts the cursor on the cell under the mouse, if there is one. Clicks
+    /// past the end of the data land on the last byte.
+    fn click(&mut self, backend: &impl Backend, e: MouseEvent) -> std::io::Result<()> {
+        let data_rows = data_rows(backend)?;
+        if e.row as usize >= data_rows {
+            return Ok(());
+        }
+        let layout = self.screen_layout(backend.size()?.0);
+        if let Some(hit) = layout.hit(e.column as usize) {
+            let line = self.line_of_row(self.start + e.row as usize);
+            self.set_cursor(line * self.layout.line_length + hit.index);
+            self.nibble = hit.nibble;
+            self.pane = hit.pane;
+        }
+        Ok(())
+    }
+
+    /// The layout lines are drawn with on a screen `columns` wide
+    fn screen_layout(&self, columns: u16) -> Layout {
+        self.layout.fit(columns as usize, self.first)
+    }
+
+    /// On screens too narrow for a whole line, scrolls sideways just enough
+    /// for the cursor's byte to be drawn. Returns whether anything moved.
+    fn scroll_horizontally(&mut self, backend: &impl Backend) -> std::io::Result<bool> {
+        let layout = self.screen_layout(backend.size()?.0);
+        let index = self.cursor % layout.line_length;
+        let old_first = self.first;
+        self.first = layout.first;
+        if index < self.first {
+            self.first = index;
+        } else if index >= self.first + layout.visible {
+            self.first = index + 1 - layout.visible;
+        }
+        Ok(self.first != old_first)
+    }
+
+    /// Scrolls just enough for the cursor's row to be visible. Returns
+    /// whether `start` changed.
+    fn scroll_to_cursor(&mut self, data_rows: usize) -> bool {
+        let row = self.cursor_row();
+        let old_start = self.start;
+        if row < self.start {
+            self.start = row;
+        } else if row >= self.start + data_rows {
+            self.start = row + 1 - data_rows;
+        }
+        self.start != old_start
+    }
+
+    /// Row the cursor is drawn on, counting from the top of the file
+    fn cursor_row(&self) -> usize {
+        self.folds
+            .row_of_line(self.cursor / self.layout.line_length)
+    }
+
+    /// Rows needed to show the whole file
+    fn total_rows(&self) -> usize {
+        self.folds.rows(self.total_lines)
+    }
+
+    /// First line drawn on `row`, counting from the top of the file
+    fn line_of_row(&self, row: usize) -> usize {
+        self.folds.line_of_row(row).0
+    }
+
+    /// Folds every long run of a single byte value, or unfolds everything
+    /// if anything is folded, keeping the same line at the top of the screen
+    fn toggle_folds(&mut self, backend: &impl Backend) -> std::io::Result<()> {
+        let top = self.line_of_row(self.start);
+        if self.folds.is_empty() {
+            self.folds = Folds::find(&self.content, self.layout.line_length, MIN_FOLD);
+            if self.folds.is_empty() {
+                self.post(Severity::Info, "no long runs of a single byte to fold");
+            }
+        } else {
+            self.folds = Folds::default();
+        }
+        self.start = self.folds.row_of_line(top);
+        let data_rows = data_rows(backend)?;
+        self.start = viewport::clamp_start(self.start, self.total_rows(), data_rows);
+        self.scroll_to_cursor(data_rows);
+        Ok(())
+    }
+
+    /// Moves to the next or previous place `stops` says is interesting
+    fn goto_interest(
+        &mut self,
+        backend: &mut impl Backend,
+        forward: bool,
+    ) -> std::io::Result<bool> {
+        let scope = self.scope();
+        let baseline = self.baseline.as_deref().map(|baseline| {
+            let end = scope.end.min(baseline.len());
+            baseline.get(scope.start..end).unwrap_or_default()
+        });
+        let sources = Sources {
+            content: &self.content[scope.clone()],
+            pattern: self.last_search.as_deref(),
+            baseline,
+            kinds: self.stops,
+        };
+        let message = if sources.is_empty() {
+            "nothing to stop at, search with / or start with --baseline"
+        } else if let Some(offset) = sources.next(self.scope_start_for(forward), forward) {
+            self.set_cursor(scope.start + offset);
+            self.nibble = 0;
+            return Ok(self.scroll_to_cursor(data_rows(backend)?));
+        } else {
+            "no matches or changes to stop at"
+        };
+        self.post(Severity::Info, message);
+        self.redraw_status(backend)?;
+        Ok(false)
+    }
+
+    fn goto_start(&mut self) -> bool {
+        self.set_cursor(0);
+        self.nibble = 0;
+        if self.start > 0 {
+            self.start = 0;
+            return true;
+        }
+        false
+    }
+
+    /// Shows the last screenful of data with the cursor on the very last byte
+    fn goto_end(&mut self, backend: &impl Backend) -> std::io::Result<bool> {
+        let data_rows = data_rows(backend)?;
+        let max_start = viewport::max_start(self.total_rows(), data_rows);
+
+        let requires_redraw = self.start < max_start;
+        if requires_redraw {
+            self.start = max_start;
+        }
+        self.set_cursor(self.content.len().saturating_sub(1));
+        self.nibble = 0;
+        Ok(requires_redraw)
+    }
+
+    /// Moves the cursor to the start of `line`, counting from 0, or to the
+    /// last line if there aren't that many
+    fn goto_line(&mut self, backend: &impl Backend, line: usize) -> std::io::Result<bool> {
+        let data_rows = data_rows(backend)?;
+        if line >= self.total_lines {
+            self.post(
+                Severity::Warning,
+                format!("only {} lines, went to the last one", self.total_lines),
+            );
+        }
+        self.set_cursor(line.saturating_mul(self.layout.line_length));
+        self.set_cursor(self.cursor - self.cursor % self.layout.line_length);
+        self.nibble = 0;
+        Ok(self.scroll_to_cursor(data_rows))
+    }
+
+    /// Screen position of the cursor as (column, row) on a screen `columns`
+    /// wide
+    fn cursor_position(&self, columns: u16, data_rows: usize) -> (u16, u16) {
+        if let Some((question, _)) = &self.question {
+            let col = question.prompt().chars().count().min(columns as usize - 1);
+            return (col as u16, data_rows as u16);
+        }
+        if let Some(view) = &self.help {
+            let col = view.status(columns as usize).1.min(columns as usize - 1);
+            return (col as u16, data_rows as u16);
+        }
+        if self.bitmap.is_some() {
+            return (0, data_rows as u16);
+        }
+        if let Some(prompt) = &self.prompt {
+            let col = 1 + prompt.line.view(columns as usize - 1).1;
+            return (col as u16, data_rows as u16);
+        }
+        let index = self.cursor % self.layout.line_length;
+        let row = match self.cursor_row().checked_sub(self.start) {
+            Some(row) if row < data_rows => row,
+            // Scrolled away with the wheel, park it out of the way at the end
+            // of the status line until it's moved
+            _ => return (columns - 1, data_rows as u16),
+        };
+        let layout = self.screen_layout(columns);
+        // Horizontal scrolling keeps the cursor's byte drawn, except while the
+        // size changes under us
+        let index = index.clamp(layout.first, layout.first + layout.visible - 1);
+        let col = layout.cursor_column(self.pane, index, self.nibble);
+        (col as u16, row as u16)
+    }
+
+    /// Redraws the whole screen
+    pub fn draw(&self, backend: &mut impl Backend) -> std::io::Result<()> {
+        let (columns, rows) = backend.size()?;
+        if too_small(columns, rows) {
+            return draw_too_small(backend, columns, rows);
+        }
+        let data_rows = data_rows(backend)?;
+        if let Some(view) = &self.help {
+            self.draw_help(backend, view, columns as usize, data_rows)?;
+            let (col, row) = self.cursor_position(columns, data_rows);
+            backend.move_to(col, row)?;
+            return backend.flush();
+        }
+        if let Some(view) = &self.bitmap {
+            self.draw_bitmap(backend, view, columns as usize, data_rows)?;
+            let (col, row) = self.cursor_position(columns, data_rows);
+            backend.move_to(col, row)?;
+            return backend.flush();
+        }
+        let layout = self.screen_layout(columns);
+        let mut line = String::with_capacity(columns as usize);
+        for i in 0..data_rows {
+            let (line_index, fold) = self.folds.line_of_row(i + self.start);
+            backend.move_to(0, i as u16)?;
+            if let Some(fold) = fold {
+                let line_length = layout.line_length;
+                format_fold(
+                    &mut line,
+                    line_length,
+                    &self.content,
+                    fold,
+                    columns as usize,
+                );
+                backend.print(&line[..OFFSET_WIDTH], Style::default())?;
+                backend.print(&line[OFFSET_WIDTH..], FOLD_STYLE)?;
+                continue;
+            }
+            format_line(
+                &mut line,
+                &layout,
+                self.printable,
+                &self.content,
+                line_index,
+                columns as usize,
+            );
+            backend.print(&line, Style::default())?;
+            if self.on_ruler(line_index) {
+                backend.move_to(0, i as u16)?;
+                backend.print(&line[..OFFSET_WIDTH - 2], RULER_STYLE)?;
+            }
+            let marks = self.mark_matches && self.last_search.is_some();
+            if self.heat.is_some() || self.baseline.is_some() || self.found.is_some() || marks {
+                self.draw_colors(backend, &layout, &line, line_index, i)?;
+            }
+        }
+        self.draw_status(backend, &mut line, columns as usize, data_rows)?;
+        let (col, row) = self.cursor_position(columns, data_rows);
+        backend.move_to(col, row)?;
+        backend.flush()
+    }
+
+    /// Draws the help screen over the whole screen, with the matches of the
+    /// last search highlighted
+    fn draw_help(
+        &self,
+        backend: &mut impl Backend,
+        view: &HelpView,
+        columns: usize,
+        data_rows: usize,
+    ) -> std::io::Result<()> {
+        let lines = view.lines();
+        let mut line = String::with_capacity(columns);
+        for row in 0..data_rows {
+            line.clear();
+            line.push_str(lines.get(view.first() + row).map_or("", String::as_str));
+            truncate(&mut line, columns);
+            pad(&mut line, columns);
+            backend.move_to(0, row as u16)?;
+            backend.print(&line, Style::default())?;
+            let pattern = view.pattern().unwrap_or_default();
+            // Help text is all ASCII, so bytes are columns
+            for found in help::matches(&line, pattern) {
+                backend.move_to(found.start as u16, row as u16)?;
+                backend.print(&line[found], HIGHLIGHT_STYLE)?;
+            }
+        }
+        self.draw_help_status(backend, columns, data_rows)
+    }
+
+    /// Draws the picture `:bitmap` makes of the data over the whole screen
+    fn draw_bitmap(
+        &self,
+        backend: &mut impl Backend,
+        view: &BitmapView,
+        columns: usize,
+        data_rows: usize,
+    ) -> std::io::Result<()> {
+        let mut text = String::with_capacity(columns);
+        for row in 0..data_rows {
+            backend.move_to(0, row as u16)?;
+            let cells = view.row(&self.content, row, columns);
+            for run in cells.chunk_by(|a, b| a.1 == b.1) {
+                text.clear();
+                text.extend(run.iter().map(|(c, _)| c));
+                backend.print(&text, run[0].1)?;
+            }
+            text.clear();
+            pad(&mut text, columns - cells.len());
+            backend.print(&text, Style::default())?;
+        }
+        self.draw_help_status(backend, columns, data_rows)
+    }
+
+    /// The status line of the help or bitmap screen, whichever is open
+    fn draw_help_status(
+        &self,
+        backend: &mut impl Backend,
+        columns: usize,
+        row: usize,
+    ) -> std::io::Result<()> {
+        let mut line = match (&self.help, &self.bitmap) {
+            (Some(view), _) => view.status(columns).0,
+            (None, Some(view)) => view.status(),
+            (None, None) => return Ok(()),
+        };
+        truncate(&mut line, columns);
+        pad(&mut line, columns);
+        backend.move_to(0, row as u16)?;
+        backend.print(&line, Style::default())
+    }
+
+    /// Whether `line` has a multiple of the ruler interval on it
+    fn on_ruler(&self, line: usize) -> bool {
+        let line_length = self.layout.line_length;
+        let line_start = line * line_length;
+        self.ruler > 0
+            && line_start < self.content.len()
+            && line_start.div_ceil(self.ruler) * self.ruler < line_start + line_length
+    }
+
+    /// How the cell for the byte at `offset` looks. Bytes that differ from
+    /// the baseline get their text tinted, the heat map sets the background,
+    /// `marked` bytes of matches of the last search are highlighted over
+    /// both and the match the search is showing over everything.
+    fn byte_style(&self, offset: usize, byte: u8, marked: bool) -> Style {
+        let mut style = Style::default();
+        if let Some(heat) = &self.heat {
+            if let Some(bg) = heat.color(byte) {
+                style.fg = Some(Color::White);
+                style.bg = Some(bg);
+            }
+        }
+        if let Some(baseline) = &self.baseline {
+            if baseline.get(offset) != Some(&byte) {
+                style.fg = Some(Color::Yellow);
+            }
+        }
+        if marked {
+            style = MARK_STYLE;
+        }
+        if self
+            .found
+            .as_ref()
+            .is_some_and(|found| found.contains(&offset))
+        {
+            style = HIGHLIGHT_STYLE;
+        }
+        style
+    }
+
+    /// Colors the cells of an already drawn `line`, which is line `line_index`
+    /// of the file, on screen row `row`. One print per cell so the gaps keep
+    /// their default look.
+    fn draw_colors(
+        &self,
+        backend: &mut impl Backend,
+        layout: &Layout,
+        line: &str,
+        line_index: usize,
+        row: usize,
+    ) -> std::io::Result<()> {
+        let line_start = line_index * layout.line_length + layout.first;
+        let bytes = self.content.get(line_start..).unwrap_or_default();
+        let bytes = &bytes[..bytes.len().min(layout.visible)];
+        let marks = self.marks(line_start..line_start + bytes.len());
+        // Hex cells after the glyphs are further into `line` than their
+        // column when glyphs take more than a byte, as in `format_line`
+        let skew = if layout.ascii && layout.ascii_left {
+            line.len() - line.chars().count()
+        } else {
+            0
+        };
+        for (i, &byte) in bytes.iter().enumerate() {
+            let offset = line_start + i;
+            let marked = marks.iter().any(|mark| mark.contains(&offset));
+            let style = self.byte_style(offset, byte, marked);
+            if style == Style::default() {
+                continue;
+            }
+            let index = layout.first + i;
+            let hex = layout.hex_column(index);
+            backend.move_to(hex as u16, row as u16)?;
+            let text = hex + skew..hex + skew + layout.cell_width() - 1;
+            backend.print(&line[text], style)?;
+            if layout.ascii {
+                let ascii = layout.ascii_column(index);
+                let glyph = self.printable.glyph(byte);
+                backend.move_to(ascii as u16, row as u16)?;
+                backend.print(glyph.encode_utf8(&mut [0; 4]), style)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The part of the data searches and scans look at, all of it unless
+    /// `:scope` says otherwise
+    fn scope(&self) -> Range<usize> {
+        let len = self.content.len();
+        match &self.scope {
+            Some(scope) => scope.start.min(len)..scope.end.min(len),
+            None => 0..len,
+        }
+    }
+
+    /// Where in the scope a search from the cursor starts. From outside the
+    /// scope that's its end going forward, so the first hit comes first, and
+    /// its start going back.
+    fn scope_start_for(&self, forward: bool) -> usize {
+        let scope = self.scope();
+        if scope.contains(&self.cursor) {
+            self.cursor - scope.start
+        } else if forward {
+            scope.len().saturating_sub(1)
+        } else {
+            0
+        }
+    }
+
+    /// The matches of the last search that have bytes in `range`, while
+    /// they're highlighted. Only the bytes around `range` are searched, so
+    /// drawing a screen costs the same anywhere in the file.
+    fn marks(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        let Some(needle) = self.last_search.as_deref().filter(|_| self.mark_matches) else {
+            return Vec::new();
+        };
+        let start = range.start.saturating_sub(needle.len() - 1);
+        let end = (range.end + needle.len() - 1).min(self.content.len());
+        let Some(around) = self.content.get(start..end) else {
+            return Vec::new();
+        };
+        around
+            .windows(needle.len())
+            .enumerate()
+            .filter(|(_, window)| *window == needle)
+            .map(|(i, _)| start + i..start + i + needle.len())
+            .collect()
+    }
+
+    fn status_values(&self) -> Values<'_> {
+        Values {
+            file: &self.file_name,
+            offset: self.cursor,
+            byte: self.content.get(self.cursor).copied(),
+            size: self.content.len(),
+            line: self.cursor / self.layout.line_length,
+        }
+    }
+
+    /// Draws just the status line, for commands that only show a message
+    fn redraw_status(&self, backend: &mut impl Backend) -> std::io::Result<()> {
+        let columns = backend.size()?.0 as usize;
+        let row = data_rows(backend)?;
+        self.draw_status(backend, &mut String::new(), columns, row)
+    }
+
+    /// The scope, the byte, the record and keys typed so far, for the
+    /// bottom right corner like in vim
+    fn corner(&self) -> String {
+        let mut corner = String::new();
+        if let Some(scope) = &self.scope {
+            let _ = write!(corner, " [{:#x}..{:#x}]", scope.start, scope.end);
+        }
+        if self.byte_readout {
+            let byte = self.content.get(self.cursor).copied();
+            let _ = write!(corner, " {}", status::byte_readout(byte));
+        }
+        if let Some(record) = self.cursor.checked_div(self.record_size) {
+            let _ = write!(corner, " record {record}");
+        }
+        if let Some(count) = self.count {
+            let _ = write!(corner, " {count}");
+        }
+        corner
+    }
+
+    fn draw_status(
+        &self,
+        backend: &mut impl Backend,
+        line: &mut String,
+        columns: usize,
+        row: usize,
+    ) -> std::io::Result<()> {
+        line.clear();
+        let mut style = Style::default();
+        if let Some((question, _)) = &self.question {
+            line.push_str(&question.prompt());
+        } else if let Some(prompt) = &self.prompt {
+            line.push(prompt.leader());
+            line.push_str(&prompt.line.view(columns.saturating_sub(1)).0);
+            if let Some(error) = &prompt.error {
+                let _ = write!(line, "  ({error})");
+            }
+        } else if let Some((message, severity)) = self.messages.current() {
+            line.push_str(message);
+            style = severity.style();
+        }
+        // Prompts get the whole line, so their text scrolls to keep the
+        // cursor on screen
+        let pending = if self.prompt.is_some() || self.question.is_some() {
+            String::new()
+        } else {
+            self.corner()
+        };
+        let room = columns.saturating_sub(pending.len());
+        if line.is_empty() {
+            match &self.status_format {
+                Some(format) => line.push_str(&format.render(&self.status_values(), room)),
+                None => line.push_str("Press 'q' to quit"),
+            }
+        }
+        // Messages can have any text in them, like what `e` decodes
+        let width = truncate_to_width(line, room);
+        backend.move_to(0, row as u16)?;
+        backend.print(line, style)?;
+        // Only the text takes the message's style
+        line.clear();
+        pad(line, room - width);
+        line.push_str(&pending);
+        truncate(line, columns - width);
+        backend.print(line, Style::default())
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    /// The file to edit couldn't be read
+    Open {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// Setting up, drawing on or reading from the terminal failed
+    Terminal(std::io::Error),
+    /// Something the user typed (arguments, prompts, config) didn't make sense
+    Parse(String),
+    /// An external command given to filter the data didn't run or failed
+    Filter { command: String, message: String },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Open { path, source } => {
+                write!(f, "cannot open '{}': {source}", path.display())
+            }
+            Error::Terminal(source) => write!(f, "terminal error: {source}"),
+            Error::Parse(message) => f.write_str(message),
+            Error::Filter { command, message } => write!(f, "'{command}' failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Open { source, .. } | Error::Terminal(source) => Some(source),
+            Error::Parse(_) | Error::Filter { .. } => None,
+        }
+    }
+}
+
+// Most of the I/O the editor does is terminal I/O, so that's what a bare
+// io::Error means unless the call site says otherwise
+impl From<std::io::Error> for Error {
+    fn from(source: std::io::Error) -> Self {
+        Error::Terminal(source)
+    }
+}
@@ -0,0 +1,115 @@
+//! In-memory stand-ins for the terminal, so the editor can be driven and
+//! inspected from tests.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+use crate::backend::{EventSource, MemoryBackend};
+use crate::editor::{Editor, Outcome};
+
+/// The screen tests draw on and inspect
+pub type TestBackend = MemoryBackend;
+
+/// Hands out a fixed list of events, then nothing.
+#[derive(Default)]
+pub struct ScriptedEvents {
+    events: VecDeque<Event>,
+}
+
+impl ScriptedEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, event: Event) -> &mut Self {
+        self.events.push_back(event);
+        self
+    }
+
+    /// Queues a key press for every character of `keys`
+    pub fn keys(&mut self, keys: &str) -> &mut Self {
+        for c in keys.chars() {
+            self.key(KeyCode::Char(c), KeyModifiers::NONE);
+        }
+        self
+    }
+
+    pub fn key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> &mut Self {
+        self.push(Event::Key(KeyEvent::new(code, modifiers)))
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) -> &mut Self {
+        self.push(Event::Resize(cols, rows))
+    }
+
+    pub fn peek(&self) -> Option<&Event> {
+        self.events.front()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Takes the first event and everything after it up to the next resize
+    fn take_until_resize(&mut self) -> ScriptedEvents {
+        let mut batch = ScriptedEvents::new();
+        batch.events.extend(self.events.pop_front());
+        while let Some(event) = self.events.pop_front() {
+            if let Event::Resize(_, _) = event {
+                self.events.push_front(event);
+                break;
+            }
+            batch.events.push_back(event);
+        }
+        batch
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn next_event(&mut self, _timeout: Duration) -> std::io::Result<Option<Event>> {
+        Ok(self.events.pop_front())
+    }
+}
+
+/// A fake screen and keyboard wired together, so resize events also change
+/// the size of the screen before the editor sees them.
+pub struct FakeTerminal {
+    pub backend: TestBackend,
+    pub events: ScriptedEvents,
+}
+
+impl FakeTerminal {
+    pub fn new(cols: u16, rows: u16) -> Self {
+        FakeTerminal {
+            backend: TestBackend::new(cols, rows),
+            events: ScriptedEvents::new(),
+        }
+    }
+
+    /// Feeds every queued event to the editor, stopping early if it quits.
+    /// The editor reads all the events it can at once, so they're handed out
+    /// one terminal size at a time, like a real terminal has already changed
+    /// size by the time the resize event can be read.
+    pub fn run(&mut self, editor: &mut Editor) -> std::io::Result<Outcome> {
+        while let Some(event) = self.events.peek() {
+            if let Event::Resize(cols, rows) = *event {
+                self.backend.resize(cols, rows);
+            }
+            let mut batch = self.events.take_until_resize();
+            while !batch.is_empty() {
+                match editor.tick(&mut self.backend, &mut batch)? {
+                    Outcome::Continue => {}
+                    outcome => {
+                        // Leave whatever wasn't read for later
+                        batch.events.append(&mut self.events.events);
+                        self.events = batch;
+                        return Ok(outcome);
+                    }
+                }
+            }
+        }
+        Ok(Outcome::Continue)
+    }
+}
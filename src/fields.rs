@@ -0,0 +1,312 @@
+//! Decoding the bytes at the cursor as a packed struct described by a short
+//! layout string such as `u32 le, u16 be, u8[6]`.
+
+use std::fmt::Write;
+
+use crate::error::{Error, Result};
+use crate::time;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    /// Seconds since 1970, unsigned
+    Unix32,
+    /// Seconds since 1970, signed
+    Unix64,
+    /// Milliseconds since 1970, signed
+    UnixMillis,
+    /// Windows FILETIME, 100 ns ticks since 1601
+    FileTime,
+}
+
+impl Kind {
+    /// The kinds that are timestamps, for `:time`
+    pub const TIMES: [Kind; 4] = [Kind::Unix32, Kind::Unix64, Kind::UnixMillis, Kind::FileTime];
+
+    const ALL: [Kind; 14] = [
+        Kind::U8,
+        Kind::I8,
+        Kind::U16,
+        Kind::I16,
+        Kind::U32,
+        Kind::I32,
+        Kind::U64,
+        Kind::I64,
+        Kind::F32,
+        Kind::F64,
+        Kind::Unix32,
+        Kind::Unix64,
+        Kind::UnixMillis,
+        Kind::FileTime,
+    ];
+
+    fn parse(name: &str) -> Option<Kind> {
+        Kind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::U8 => "u8",
+            Kind::I8 => "i8",
+            Kind::U16 => "u16",
+            Kind::I16 => "i16",
+            Kind::U32 => "u32",
+            Kind::I32 => "i32",
+            Kind::U64 => "u64",
+            Kind::I64 => "i64",
+            Kind::F32 => "f32",
+            Kind::F64 => "f64",
+            Kind::Unix32 => "unix32",
+            Kind::Unix64 => "unix64",
+            Kind::UnixMillis => "unixms",
+            Kind::FileTime => "filetime",
+        }
+    }
+
+    /// Size in bytes
+    pub fn size(self) -> usize {
+        match self {
+            Kind::U8 | Kind::I8 => 1,
+            Kind::U16 | Kind::I16 => 2,
+            Kind::U32 | Kind::I32 | Kind::F32 | Kind::Unix32 => 4,
+            Kind::U64
+            | Kind::I64
+            | Kind::F64
+            | Kind::Unix64
+            | Kind::UnixMillis
+            | Kind::FileTime => 8,
+        }
+    }
+
+    /// Formats one value from exactly `self.size()` bytes
+    fn format(self, out: &mut String, bytes: &[u8], big_endian: bool) {
+        let mut buf = [0; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        if big_endian {
+            buf[..bytes.len()].reverse();
+        }
+        // `buf` now holds the value little endian, zero extended
+        let raw = u64::from_le_bytes(buf);
+        // Writing to a String can't fail
+        let _ = match self {
+            Kind::U8 | Kind::U16 | Kind::U32 | Kind::U64 => write!(out, "{raw}"),
+            Kind::I8 => write!(out, "{}", raw as u8 as i8),
+            Kind::I16 => write!(out, "{}", raw as u16 as i16),
+            Kind::I32 => write!(out, "{}", raw as u32 as i32),
+            Kind::I64 => write!(out, "{}", raw as i64),
+            Kind::F32 => write!(out, "{}", f32::from_bits(raw as u32)),
+            Kind::F64 => write!(out, "{}", f64::from_bits(raw)),
+            Kind::Unix32 | Kind::Unix64 | Kind::UnixMillis | Kind::FileTime => {
+                let (seconds, fraction, digits) = match self {
+                    // Unix32 was zero extended, so it's unsigned
+                    Kind::Unix32 | Kind::Unix64 => (raw as i64, 0, 0),
+                    Kind::UnixMillis => {
+                        let millis = raw as i64;
+                        (millis.div_euclid(1000), millis.rem_euclid(1000) as u32, 3)
+                    }
+                    _ => {
+                        let seconds = (raw / 10_000_000) as i64 - time::FILETIME_EPOCH;
+                        (seconds, (raw % 10_000_000) as u32, 7)
+                    }
+                };
+                match time::format(seconds, fraction, digits) {
+                    Some(date) => write!(out, "{date}"),
+                    None => write!(out, "out of range ({raw:#x})"),
+                }
+            }
+        };
+    }
+}
+
+/// One field of a layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldSpec {
+    pub kind: Kind,
+    pub big_endian: bool,
+    /// Number of elements for arrays like `u8[6]`, `None` for a single value
+    pub count: Option<usize>,
+}
+
+impl FieldSpec {
+    /// Size in bytes of the whole field
+    pub fn size(&self) -> usize {
+        self.kind.size() * self.count.unwrap_or(1)
+    }
+}
+
+/// Parses a comma separated list of fields, each a type (`u8` to `u64`, `i8`
+/// to `i64`, `f32`, `f64` or a timestamp: `unix32`, `unix64`, `unixms`,
+/// `filetime`), an optional `[N]` array length and an optional `le` or
+/// `be`. Fields are little endian unless they say otherwise.
+pub fn parse_layout(text: &str) -> Result<Vec<FieldSpec>> {
+    let error = |field: &str| Error::Parse(format!("invalid field '{field}' in layout '{text}'"));
+    text.split(',')
+        .map(|field| {
+            let mut words = field.split_whitespace();
+            let ty = words.next().ok_or_else(|| error(field))?;
+            let big_endian = match words.next() {
+                None | Some("le") => false,
+                Some("be") => true,
+                Some(_) => return Err(error(field)),
+            };
+            if words.next().is_some() {
+                return Err(error(field));
+            }
+            let (name, count) = match ty.split_once('[') {
+                Some((name, count)) => {
+                    let count = count
+                        .strip_suffix(']')
+                        .and_then(|count| count.parse().ok())
+                        .filter(|&count| count > 0)
+                        .ok_or_else(|| error(field))?;
+                    (name, Some(count))
+                }
+                None => (ty, None),
+            };
+            Ok(FieldSpec {
+                kind: Kind::parse(name).ok_or_else(|| error(field))?,
+                big_endian,
+                count,
+            })
+        })
+        .collect()
+}
+
+/// Decodes `bytes` field by field into one line like `u32 1, u8[2] [3, 4]`.
+/// Fields that don't fit in `bytes` are left out and the line says so.
+pub fn decode(fields: &[FieldSpec], bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let mut rest = bytes;
+    for field in fields {
+        if !out.is_empty() {
+            out.push_str(", ");
+        }
+        if rest.len() < field.size() {
+            out.push_str("(past end of file)");
+            break;
+        }
+        let (value, tail) = rest.split_at(field.size());
+        rest = tail;
+        out.push_str(field.kind.name());
+        match field.count {
+            Some(count) => {
+                let _ = write!(out, "[{count}] [");
+                for (i, element) in value.chunks(field.kind.size()).enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    field.kind.format(&mut out, element, field.big_endian);
+                }
+                out.push(']');
+            }
+            None => {
+                out.push(' ');
+                field.kind.format(&mut out, value, field.big_endian);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_layouts() {
+        let fields = parse_layout("u32 le, u16 be,u8[6], f64").unwrap();
+        assert_eq!(
+            fields,
+            [
+                FieldSpec {
+                    kind: Kind::U32,
+                    big_endian: false,
+                    count: None
+                },
+                FieldSpec {
+                    kind: Kind::U16,
+                    big_endian: true,
+                    count: None
+                },
+                FieldSpec {
+                    kind: Kind::U8,
+                    big_endian: false,
+                    count: Some(6)
+                },
+                FieldSpec {
+                    kind: Kind::F64,
+                    big_endian: false,
+                    count: None
+                },
+            ]
+        );
+        assert_eq!(fields.iter().map(FieldSpec::size).sum::<usize>(), 20);
+    }
+
+    #[test]
+    fn rejects_bad_layouts() {
+        for bad in [
+            "",
+            "u24",
+            "u32 middle",
+            "u8[0]",
+            "u8[x]",
+            "u8[3",
+            "u16 le be",
+            "u8,,u8",
+        ] {
+            assert!(parse_layout(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn decodes_fields_in_order() {
+        let fields = parse_layout("u32, u16 be, i8, u8[3]").unwrap();
+        let bytes = [0x01, 0x02, 0x00, 0x00, 0x12, 0x34, 0xff, 7, 8, 9];
+        assert_eq!(
+            decode(&fields, &bytes),
+            "u32 513, u16 4660, i8 -1, u8[3] [7, 8, 9]"
+        );
+    }
+
+    #[test]
+    fn decodes_signed_and_float() {
+        let fields = parse_layout("i16 be, f32").unwrap();
+        let mut bytes = vec![0xff, 0xfe];
+        bytes.extend(1.5f32.to_le_bytes());
+        assert_eq!(decode(&fields, &bytes), "i16 -2, f32 1.5");
+    }
+
+    #[test]
+    fn decodes_timestamps() {
+        let fields = parse_layout("unix32 be, unixms, filetime").unwrap();
+        let mut bytes = 1_709_211_909u32.to_be_bytes().to_vec();
+        bytes.extend((-1i64).to_le_bytes());
+        bytes.extend(133_536_855_090_420_000u64.to_le_bytes());
+        assert_eq!(
+            decode(&fields, &bytes),
+            "unix32 2024-02-29 13:05:09 UTC, unixms 1969-12-31 23:59:59.999 UTC, \
+             filetime 2024-02-29 13:05:09.0420000 UTC"
+        );
+        let fields = parse_layout("unix64").unwrap();
+        assert_eq!(
+            decode(&fields, &i64::MAX.to_le_bytes()),
+            "unix64 out of range (0x7fffffffffffffff)"
+        );
+    }
+
+    #[test]
+    fn stops_at_end_of_data() {
+        let fields = parse_layout("u8, u32").unwrap();
+        assert_eq!(decode(&fields, &[1, 2, 3]), "u8 1, (past end of file)");
+    }
+}
@@ -0,0 +1,84 @@
+//! Running data through an external command, like `--on-load gunzip`.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::{Error, Result};
+
+/// Runs `command` with the shell, writing `input` to its stdin, and returns
+/// what it wrote to stdout. A command that exits with an error fails with
+/// what it wrote to stderr.
+pub fn run(command: &str, input: &[u8]) -> Result<Vec<u8>> {
+    let error = |message: String| Error::Filter {
+        command: command.to_string(),
+        message,
+    };
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| error(err.to_string()))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let output = std::thread::scope(|scope| {
+        // Writing while reading, or a command that writes before it has read
+        // everything would wait on us forever once the pipes fill up
+        scope.spawn(move || {
+            // A command that doesn't read all of its input isn't wrong, so a
+            // broken pipe is left for the exit status to report
+            let _ = stdin.write_all(input);
+        });
+        child.wait_with_output()
+    });
+    let output = output.map_err(|err| error(err.to_string()))?;
+    let status = output.status;
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stderr = stderr.trim();
+        return Err(error(if stderr.is_empty() {
+            status.to_string()
+        } else {
+            format!("{status}: {stderr}")
+        }));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_data_through() {
+        assert_eq!(run("tr a-z A-Z", b"hex").unwrap(), b"HEX");
+        // More than fits in a pipe at once, both ways
+        let data = vec![7; 1 << 20];
+        assert_eq!(run("cat", &data).unwrap(), data);
+        assert_eq!(run("echo hi", &data).unwrap(), b"hi\n");
+    }
+
+    #[test]
+    fn failures_show_stderr() {
+        let err = run("echo broken >&2; exit 3", b"").unwrap_err().to_string();
+        assert!(
+            err.starts_with("'echo broken >&2; exit 3' failed: "),
+            "{err}"
+        );
+        assert!(err.ends_with("3: broken"), "{err}");
+        assert!(run("exit 1", b"").is_err());
+    }
+}
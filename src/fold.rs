@@ -0,0 +1,177 @@
+//! Folding long runs of one repeated byte into a single summary row.
+//!
+//! Only whole lines fold, so a fold is a range of lines. With folds, screen
+//! rows no longer match lines one to one: every fold takes a single row.
+
+use std::ops::Range;
+
+/// Fewest bytes a run needs before it's folded
+pub const MIN_FOLD: usize = 0x100;
+
+/// Folded line ranges, sorted and not overlapping
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Folds {
+    ranges: Vec<Range<usize>>,
+    /// Lines hidden by the folds before each fold, for the row math
+    hidden_before: Vec<usize>,
+    /// Row each fold is drawn on
+    rows: Vec<usize>,
+}
+
+impl Folds {
+    /// Folds every run of at least two whole lines, and `min_bytes` bytes,
+    /// that are all one byte value
+    pub fn find(content: &[u8], line_length: usize, min_bytes: usize) -> Self {
+        let mut folds = Folds::default();
+        let mut run: Option<(usize, u8)> = None;
+        let lines = content.chunks(line_length);
+        // An empty line past the end closes the last run
+        for (line, bytes) in lines
+            .enumerate()
+            .chain([(content.len() / line_length, &[][..])])
+        {
+            let value = (bytes.len() == line_length && bytes.iter().all(|&b| b == bytes[0]))
+                .then(|| bytes[0]);
+            if let Some((begin, run_value)) = run {
+                if value == Some(run_value) {
+                    continue;
+                }
+                let len = line - begin;
+                if len >= 2 && len * line_length >= min_bytes {
+                    folds.push(begin..line);
+                }
+            }
+            run = value.map(|value| (line, value));
+        }
+        folds
+    }
+
+    fn push(&mut self, range: Range<usize>) {
+        let hidden = self.hidden_before.last().copied().unwrap_or(0)
+            + self.ranges.last().map_or(0, |last| last.len() - 1);
+        self.rows.push(range.start - hidden);
+        self.ranges.push(range);
+        self.hidden_before.push(hidden);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Rows needed to show every one of `total_lines` lines
+    pub fn rows(&self, total_lines: usize) -> usize {
+        match (self.ranges.last(), self.hidden_before.last()) {
+            (Some(last), Some(hidden)) => total_lines - hidden - (last.len() - 1),
+            _ => total_lines,
+        }
+    }
+
+    /// The fold `line` is in
+    pub fn fold_at(&self, line: usize) -> Option<Range<usize>> {
+        let i = self.ranges.partition_point(|fold| fold.end <= line);
+        self.ranges
+            .get(i)
+            .filter(|fold| fold.contains(&line))
+            .cloned()
+    }
+
+    /// Row `line` is drawn on, which for a folded line is the fold's row
+    pub fn row_of_line(&self, line: usize) -> usize {
+        let i = self.ranges.partition_point(|fold| fold.end <= line);
+        let hidden = match self.ranges.get(i) {
+            Some(fold) if fold.start <= line => return self.rows[i],
+            _ if i > 0 => self.hidden_before[i - 1] + self.ranges[i - 1].len() - 1,
+            _ => 0,
+        };
+        line - hidden
+    }
+
+    /// First line drawn on `row`, and the fold if the row is one
+    pub fn line_of_row(&self, row: usize) -> (usize, Option<Range<usize>>) {
+        let i = self.rows.partition_point(|&fold_row| fold_row < row);
+        if self.rows.get(i) == Some(&row) {
+            let fold = self.ranges[i].clone();
+            return (fold.start, Some(fold));
+        }
+        let hidden = match i {
+            0 => 0,
+            _ => self.hidden_before[i - 1] + self.ranges[i - 1].len() - 1,
+        };
+        (row + hidden, None)
+    }
+
+    /// Unfolds the fold `line` is in, returning whether there was one
+    pub fn open(&mut self, line: usize) -> bool {
+        let Some(fold) = self.fold_at(line) else {
+            return false;
+        };
+        let ranges = std::mem::take(&mut self.ranges);
+        self.hidden_before.clear();
+        self.rows.clear();
+        for range in ranges.into_iter().filter(|range| *range != fold) {
+            self.push(range);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lines 2..6 and 8..10 of zeros, the rest counting up
+    fn data() -> Vec<u8> {
+        let mut data: Vec<u8> = (1..=12 * 4).collect();
+        data[2 * 4..6 * 4].fill(0);
+        data[8 * 4..10 * 4].fill(0xff);
+        data
+    }
+
+    #[test]
+    fn finds_uniform_lines() {
+        let folds = Folds::find(&data(), 4, 8);
+        assert_eq!(folds.ranges, [2..6, 8..10]);
+        assert_eq!(
+            Folds::find(&data(), 4, 9).ranges,
+            [Range { start: 2, end: 6 }]
+        );
+        // A run at the very end folds too, but a short last line never does
+        assert_eq!(
+            Folds::find(&[0; 10], 4, 8).ranges,
+            [Range { start: 0, end: 2 }]
+        );
+        assert!(Folds::find(&[], 4, 8).is_empty());
+    }
+
+    #[test]
+    fn runs_of_different_values_are_separate() {
+        let mut data = vec![0; 16];
+        data.extend([1; 16]);
+        assert_eq!(Folds::find(&data, 4, 8).ranges, [0..4, 4..8]);
+    }
+
+    #[test]
+    fn rows_and_lines() {
+        let folds = Folds::find(&data(), 4, 8);
+        assert_eq!(folds.rows(12), 12 - 3 - 1);
+        let rows: Vec<usize> = (0..12).map(|line| folds.row_of_line(line)).collect();
+        assert_eq!(rows, [0, 1, 2, 2, 2, 2, 3, 4, 5, 5, 6, 7]);
+        for row in 0..8 {
+            let (line, fold) = folds.line_of_row(row);
+            assert_eq!(folds.row_of_line(line), row);
+            assert_eq!(fold.is_some(), row == 2 || row == 5, "row {row}");
+        }
+        assert_eq!(folds.line_of_row(5), (8, Some(8..10)));
+        assert_eq!(folds.line_of_row(9).0, 13);
+    }
+
+    #[test]
+    fn opening_a_fold() {
+        let mut folds = Folds::find(&data(), 4, 8);
+        assert!(folds.open(3));
+        assert!(!folds.open(3));
+        assert_eq!(folds.rows(12), 11);
+        assert_eq!(folds.row_of_line(9), 8);
+        assert_eq!(folds.line_of_row(8), (8, Some(8..10)));
+    }
+}
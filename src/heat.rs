@@ -0,0 +1,86 @@
+//! Byte-frequency heat map: colors every byte by how rare its value is in the
+//! whole buffer, so odd bytes stand out against padding and repeated data.
+
+use crossterm::style::Color;
+
+/// Backgrounds from least to most rare. The most common values are left on
+/// the terminal's default background.
+const PALETTE: [Option<Color>; 5] = [
+    None,
+    Some(Color::AnsiValue(17)),
+    Some(Color::AnsiValue(54)),
+    Some(Color::AnsiValue(124)),
+    Some(Color::AnsiValue(196)),
+];
+
+/// How often each byte value occurs in a buffer
+pub struct Histogram {
+    counts: [u64; 256],
+    max: u64,
+}
+
+impl Histogram {
+    pub fn new(data: &[u8]) -> Self {
+        let mut counts = [0; 256];
+        for &byte in data {
+            counts[byte as usize] += 1;
+        }
+        let max = counts.iter().copied().max().unwrap_or(0);
+        Histogram { counts, max }
+    }
+
+    pub fn count(&self, byte: u8) -> u64 {
+        self.counts[byte as usize]
+    }
+
+    /// Background for `byte`, brighter the rarer it is
+    pub fn color(&self, byte: u8) -> Option<Color> {
+        PALETTE[self.level(byte)]
+    }
+
+    /// Index into `PALETTE`. Counts are compared on a log scale, otherwise one
+    /// dominant value (usually zero) would make everything else look rare.
+    fn level(&self, byte: u8) -> usize {
+        let count = self.count(byte);
+        if count == 0 || self.max <= 1 {
+            return 0;
+        }
+        let ratio = (count as f64).ln() / (self.max as f64).ln();
+        let last = PALETTE.len() - 1;
+        last - (ratio * last as f64).round() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        let histogram = Histogram::new(&[0, 0, 0, 7]);
+        assert_eq!(histogram.count(0), 3);
+        assert_eq!(histogram.count(7), 1);
+        assert_eq!(histogram.count(1), 0);
+    }
+
+    #[test]
+    fn rare_bytes_are_hotter() {
+        let mut data = vec![0; 10_000];
+        data.extend([1; 100]);
+        data.push(2);
+        let histogram = Histogram::new(&data);
+        assert_eq!(histogram.level(0), 0);
+        assert_eq!(histogram.level(2), PALETTE.len() - 1);
+        assert!(histogram.level(1) > histogram.level(0));
+        assert!(histogram.level(1) < histogram.level(2));
+        assert_eq!(histogram.color(0), None);
+    }
+
+    #[test]
+    fn uniform_data_is_not_colored() {
+        let data: Vec<u8> = (0..=255).collect();
+        let histogram = Histogram::new(&data);
+        assert!((0..=255).all(|byte| histogram.color(byte).is_none()));
+        assert!(Histogram::new(&[]).color(0).is_none());
+    }
+}
@@ -0,0 +1,329 @@
+//! The help screen: every key binding and command, built from the tables the
+//! editor itself goes by so it can't fall out of date.
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::command::COMMANDS;
+use crate::keymap::{self, Action, Category, BINDINGS};
+use crate::keys;
+use crate::line::LineEdit;
+
+/// Every binding by category, one line per action with all of its keys, then
+/// every command. Backs both the help screen and `--help-keys`.
+pub fn help_lines() -> Vec<String> {
+    let mut actions: Vec<(Action, Vec<String>)> = Vec::new();
+    for &(code, modifiers, action) in BINDINGS {
+        let name = keys::key_name(code, modifiers);
+        match actions.iter_mut().find(|(other, _)| *other == action) {
+            Some((_, names)) => names.push(name),
+            None => actions.push((action, vec![name])),
+        }
+    }
+    let mut sections: Vec<(&str, Vec<(String, &str)>)> = Category::ALL
+        .into_iter()
+        .map(|category| {
+            let entries = actions
+                .iter()
+                .filter(|(action, _)| action.category() == category)
+                .map(|(action, names)| (names.join(" "), action.description()))
+                .collect();
+            (category.name(), entries)
+        })
+        .collect();
+    let commands = COMMANDS
+        .iter()
+        .map(|(names, arg, description)| {
+            let names: Vec<String> = names.iter().map(|name| format!(":{name}")).collect();
+            let usage = format!("{} {arg}", names.join(" "));
+            (usage.trim_end().to_string(), *description)
+        })
+        .collect();
+    sections.push(("Commands", commands));
+
+    let width = sections
+        .iter()
+        .flat_map(|(_, entries)| entries)
+        .map(|(keys, _)| keys.len())
+        .max()
+        .unwrap_or(0);
+    let mut lines = Vec::new();
+    for (name, entries) in sections {
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(name.to_string());
+        for (keys, description) in entries {
+            lines.push(format!("  {keys:width$}  {description}"));
+        }
+    }
+    lines
+}
+
+/// What a key did to the help screen, or the bitmap screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HelpStep {
+    Close,
+    Redraw,
+    /// Only the status line changed, if anything
+    Status,
+}
+
+/// The open help screen
+pub struct HelpView {
+    lines: Vec<String>,
+    /// First line shown
+    first: usize,
+    /// Text typed after `/` while the search prompt is open
+    search: Option<LineEdit>,
+    /// What the last search looked for, highlighted and repeated by `n`
+    pattern: Option<String>,
+    /// Line the last search stopped on, where `n` carries on from
+    found: Option<usize>,
+    not_found: bool,
+}
+
+impl Default for HelpView {
+    fn default() -> Self {
+        HelpView::new(help_lines())
+    }
+}
+
+impl HelpView {
+    /// A view of any text, paged and searched like the help
+    pub fn new(lines: Vec<String>) -> Self {
+        HelpView {
+            lines,
+            first: 0,
+            search: None,
+            pattern: None,
+            found: None,
+            not_found: false,
+        }
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn first(&self) -> usize {
+        self.first
+    }
+
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern.as_deref()
+    }
+
+    /// Text for a status line `width` columns wide, and the column to put
+    /// the cursor in
+    pub fn status(&self, width: usize) -> (String, usize) {
+        match &self.search {
+            Some(line) => {
+                let (text, cursor) = line.view(width.saturating_sub(1));
+                (format!("/{text}"), 1 + cursor)
+            }
+            None if self.not_found => {
+                let pattern = self.pattern.as_deref().unwrap_or_default();
+                (format!("'{pattern}' not found"), 0)
+            }
+            None => (
+                "j/k scroll, space pages, / searches, n finds the next, any other key closes"
+                    .to_string(),
+                0,
+            ),
+        }
+    }
+
+    /// Moves by `lines`, keeping a screen of `rows` rows filled
+    pub fn scroll(&mut self, lines: isize, rows: usize) -> HelpStep {
+        let max_first = self.lines.len().saturating_sub(rows);
+        let old_first = self.first;
+        self.first = self.first.saturating_add_signed(lines).min(max_first);
+        if self.first == old_first {
+            HelpStep::Status
+        } else {
+            HelpStep::Redraw
+        }
+    }
+
+    /// Reacts to `key` on a screen with `rows` rows for the help
+    pub fn key(&mut self, key: &KeyEvent, rows: usize) -> HelpStep {
+        self.not_found = false;
+        if let Some(line) = &mut self.search {
+            match key.code {
+                KeyCode::Esc => self.search = None,
+                // Backspacing over the `/` closes the search
+                KeyCode::Backspace if line.text().is_empty() => self.search = None,
+                KeyCode::Enter => {
+                    let line = self.search.take().unwrap_or_default();
+                    if !line.text().is_empty() {
+                        self.pattern = Some(line.text().to_string());
+                    }
+                    return self.find_next(rows);
+                }
+                _ => {
+                    line.key(key);
+                }
+            }
+            return HelpStep::Status;
+        }
+        let page = rows.max(1) as isize;
+        match key.code {
+            KeyCode::Char('/') => {
+                self.search = Some(LineEdit::default());
+                return HelpStep::Status;
+            }
+            KeyCode::Char('n') => return self.find_next(rows),
+            _ => {}
+        }
+        // Scrolling by hand starts the next search from the top of the screen
+        self.found = None;
+        match key.code {
+            KeyCode::Char(' ') | KeyCode::PageDown => return self.scroll(page, rows),
+            KeyCode::PageUp => return self.scroll(-page, rows),
+            _ => {}
+        }
+        match keymap::action_for(key) {
+            Some(Action::Down) => self.scroll(1, rows),
+            Some(Action::Up) => self.scroll(-1, rows),
+            Some(Action::Top) => self.scroll(isize::MIN, rows),
+            Some(Action::Bottom) => self.scroll(isize::MAX, rows),
+            _ => HelpStep::Close,
+        }
+    }
+
+    /// Scrolls to the next line that has the pattern in it, ignoring case and
+    /// starting over from the top. The search starts after the line the last
+    /// one found, or after the first line shown.
+    fn find_next(&mut self, rows: usize) -> HelpStep {
+        let Some(pattern) = &self.pattern else {
+            return HelpStep::Status;
+        };
+        let pattern = pattern.to_lowercase();
+        let len = self.lines.len();
+        let found = (1..=len)
+            .map(|i| (self.found.unwrap_or(self.first) + i) % len)
+            .find(|&i| self.lines[i].to_lowercase().contains(&pattern));
+        match found {
+            Some(line) => {
+                // Near the end the line can't go to the top, the highlight
+                // still shows it
+                self.first = 0;
+                self.scroll(line as isize, rows);
+                self.found = Some(line);
+            }
+            None => {
+                self.found = None;
+                self.not_found = true;
+            }
+        }
+        // The highlight changes with the pattern
+        HelpStep::Redraw
+    }
+}
+
+/// Where `pattern` occurs in `line`, ignoring ASCII case, as byte ranges
+pub fn matches(line: &str, pattern: &str) -> Vec<std::ops::Range<usize>> {
+    if pattern.is_empty() || !pattern.is_ascii() {
+        return Vec::new();
+    }
+    let haystack = line.to_ascii_lowercase();
+    let needle = pattern.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(at, _)| at..at + needle.len())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(view: &mut HelpView, keys: &str) -> HelpStep {
+        let mut step = HelpStep::Status;
+        for key in keys::parse_keys(keys).unwrap() {
+            step = view.key(&key, 10);
+        }
+        step
+    }
+
+    #[test]
+    fn lists_every_binding_and_command() {
+        let lines = help_lines();
+        for &(code, modifiers, action) in BINDINGS {
+            let name = keys::key_name(code, modifiers);
+            let line = lines
+                .iter()
+                .find(|line| line.ends_with(action.description()))
+                .unwrap();
+            assert!(line.split_whitespace().any(|key| key == name), "{name}");
+        }
+        for (names, _, description) in COMMANDS {
+            let usage = format!("  :{} ", names[0]);
+            let listed = lines
+                .iter()
+                .any(|line| line.starts_with(&usage) && line.ends_with(description));
+            assert!(listed, "{usage}");
+        }
+        assert_eq!(lines[0], "Movement");
+        assert!(lines[1].starts_with("  h <Left> "));
+        assert!(lines.iter().any(|line| line.starts_with("  :goto OFFSET ")));
+    }
+
+    #[test]
+    fn scrolls_and_pages() {
+        let mut view = HelpView::default();
+        let last = view.lines().len() - 10;
+        assert_eq!(press(&mut view, "k"), HelpStep::Status);
+        assert_eq!(press(&mut view, "jj"), HelpStep::Redraw);
+        assert_eq!(view.first(), 2);
+        press(&mut view, "<Space>");
+        assert_eq!(view.first(), 12.min(last));
+        press(&mut view, "G");
+        assert_eq!(view.first(), last);
+        press(&mut view, "g");
+        assert_eq!(view.first(), 0);
+        assert_eq!(press(&mut view, "x"), HelpStep::Close);
+    }
+
+    #[test]
+    fn searches() {
+        let mut view = HelpView::default();
+        assert_eq!(press(&mut view, "/GOTO"), HelpStep::Status);
+        assert_eq!(view.status(80).0, "/GOTO");
+        press(&mut view, "<CR>");
+        let line = view
+            .lines()
+            .iter()
+            .position(|l| l.contains(":goto"))
+            .unwrap();
+        assert_eq!(view.first(), line.min(view.lines().len() - 10));
+        assert_eq!(view.pattern(), Some("GOTO"));
+
+        // `n` visits every match in turn, even the ones near the end that
+        // can't be scrolled to the top
+        let matching: Vec<usize> = (0..view.lines().len())
+            .filter(|&i| view.lines()[i].contains("line"))
+            .collect();
+        assert!(matching.len() > 1);
+        press(&mut view, "/line<CR>");
+        let mut found = vec![view.found.unwrap()];
+        for _ in 1..matching.len() {
+            press(&mut view, "n");
+            found.push(view.found.unwrap());
+        }
+        found.sort();
+        assert_eq!(found, matching);
+
+        press(&mut view, "/nothing like this<CR>");
+        assert_eq!(view.status(80).0, "'nothing like this' not found");
+        // Keys typed into the search don't close the help, Esc drops it
+        assert_eq!(press(&mut view, "/q<Esc>"), HelpStep::Status);
+        assert_eq!(view.pattern(), Some("nothing like this"));
+    }
+
+    #[test]
+    fn finds_matches_ignoring_case() {
+        assert_eq!(matches("Go to :goto", "GO"), [0..2, 7..9]);
+        assert!(matches("anything", "").is_empty());
+    }
+}
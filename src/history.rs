@@ -0,0 +1,156 @@
+//! What was entered on the `:` and `/` prompts, kept between sessions in the
+//! data directory.
+
+use std::path::{Path, PathBuf};
+
+/// Entries kept per prompt, older ones are dropped
+pub const MAX_ENTRIES: usize = 200;
+
+/// Entries of one prompt, oldest first
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct History {
+    entries: Vec<String>,
+}
+
+impl History {
+    /// Reads one entry per line
+    pub fn parse(text: &str) -> Self {
+        let mut history = History::default();
+        for line in text.lines() {
+            history.push(line);
+        }
+        history
+    }
+
+    /// One entry per line, the way `parse` reads them
+    pub fn to_text(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| format!("{entry}\n"))
+            .collect()
+    }
+
+    /// Adds `entry` as the most recent one, unless it's empty or the same as
+    /// the one before it
+    pub fn push(&mut self, entry: &str) {
+        if entry.is_empty() || self.entries.last().is_some_and(|last| last == entry) {
+            return;
+        }
+        if self.entries.len() == MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        self.entries.push(entry.to_string());
+    }
+
+    /// Entry `back` steps back from the most recent one, which is 0
+    pub fn get(&self, back: usize) -> Option<&str> {
+        let index = self.entries.len().checked_sub(back + 1)?;
+        Some(&self.entries[index])
+    }
+
+    /// How far back the next entry older than `back` with `filter` in it is,
+    /// or the most recent one with it if `back` is `None`
+    pub fn older(&self, back: Option<usize>, filter: &str) -> Option<usize> {
+        let from = back.map_or(0, |back| back + 1);
+        (from..self.entries.len()).find(|&back| self.get(back).is_some_and(|e| e.contains(filter)))
+    }
+
+    /// Like `older`, but towards the most recent entry. `None` once there are
+    /// no newer ones.
+    pub fn newer(&self, back: usize, filter: &str) -> Option<usize> {
+        (0..back)
+            .rev()
+            .find(|&back| self.get(back).is_some_and(|e| e.contains(filter)))
+    }
+}
+
+/// The history of each prompt
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Histories {
+    pub command: History,
+    pub search: History,
+}
+
+const COMMAND_FILE: &str = "command-history";
+const SEARCH_FILE: &str = "search-history";
+
+impl Histories {
+    /// Reads the histories saved in `dir`. Missing files are empty histories.
+    pub fn load(dir: &Path) -> std::io::Result<Self> {
+        let read = |name| match std::fs::read_to_string(dir.join(name)) {
+            Ok(text) => Ok(History::parse(&text)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(History::default()),
+            Err(err) => Err(err),
+        };
+        Ok(Histories {
+            command: read(COMMAND_FILE)?,
+            search: read(SEARCH_FILE)?,
+        })
+    }
+
+    /// Writes both histories to `dir`, creating it if needed
+    pub fn save(&self, dir: &Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join(COMMAND_FILE), self.command.to_text())?;
+        std::fs::write(dir.join(SEARCH_FILE), self.search.to_text())
+    }
+}
+
+/// Where files that outlive a session go: `$XDG_DATA_HOME/hex-editor`,
+/// falling back to `~/.local/share/hex-editor`, or `%APPDATA%\hex-editor` on
+/// Windows
+pub fn data_dir() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+    let base = if cfg!(windows) {
+        PathBuf::from(var("APPDATA")?)
+    } else {
+        match var("XDG_DATA_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(var("HOME")?).join(".local/share"),
+        }
+    };
+    Some(base.join("hex-editor"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_repeats_and_drops_the_oldest() {
+        let mut history = History::parse("goto 1\ngoto 1\n\ncols 8\ngoto 1\n");
+        assert_eq!(history.to_text(), "goto 1\ncols 8\ngoto 1\n");
+        assert_eq!(history.get(0), Some("goto 1"));
+        assert_eq!(history.get(1), Some("cols 8"));
+        assert_eq!(history.get(3), None);
+
+        for i in 0..MAX_ENTRIES {
+            history.push(&format!("goto {i}"));
+        }
+        assert_eq!(history.get(MAX_ENTRIES - 1), Some("goto 0"));
+        assert_eq!(history.get(MAX_ENTRIES), None);
+    }
+
+    #[test]
+    fn steps_through_matching_entries() {
+        let history = History::parse("goto 1\ncols 8\ngoto 2\nq\n");
+        assert_eq!(history.older(None, ""), Some(0));
+        assert_eq!(history.older(None, "goto"), Some(1));
+        assert_eq!(history.older(Some(1), "goto"), Some(3));
+        assert_eq!(history.older(Some(3), "goto"), None);
+        assert_eq!(history.newer(3, "goto"), Some(1));
+        assert_eq!(history.newer(1, "goto"), None);
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let dir = std::env::temp_dir().join(format!("hex-editor-history-{}", std::process::id()));
+        assert_eq!(Histories::load(&dir).unwrap(), Histories::default());
+        let mut histories = Histories::default();
+        histories.command.push("cols 8");
+        histories.search.push("de ad");
+        histories.save(&dir).unwrap();
+        assert_eq!(Histories::load(&dir).unwrap(), histories);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
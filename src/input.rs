@@ -0,0 +1,120 @@
+//! Where the data comes from: a file or named pipe, stdin, or a file
+//! descriptor left open by whoever started us.
+
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+
+use crate::error::{Error, Result};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Input {
+    /// A file, or anything else that can be opened by name like a FIFO
+    Path(PathBuf),
+    /// `-` on the command line
+    #[default]
+    Stdin,
+    /// `--fd N`, only on Unix
+    Fd(usize),
+}
+
+impl Input {
+    /// How the input is called in messages and the status line
+    pub fn name(&self) -> String {
+        match self {
+            Input::Path(path) => path.display().to_string(),
+            Input::Stdin => "stdin".to_string(),
+            Input::Fd(fd) => format!("fd {fd}"),
+        }
+    }
+
+    /// The name without any directories, for the window title
+    pub fn short_name(&self) -> String {
+        match self {
+            Input::Path(path) => path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+            _ => self.name(),
+        }
+    }
+
+    /// Reads everything there is to read. Pipes are read until whatever
+    /// writes to them closes them.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        let error = |source| Error::Open {
+            path: PathBuf::from(self.name()),
+            source,
+        };
+        match self {
+            // Reading one fails with an OS error that doesn't say much
+            Input::Path(path) if path.is_dir() => Err(error(std::io::Error::new(
+                std::io::ErrorKind::IsADirectory,
+                "it's a directory, not a file",
+            ))),
+            Input::Path(path) => std::fs::read(path).map_err(error),
+            Input::Stdin => {
+                let mut stdin = std::io::stdin();
+                // The keyboard is on it, nobody would ever get to type
+                if stdin.is_terminal() {
+                    return Err(Error::Parse(
+                        "stdin is a terminal, pipe the data in or give a file".to_string(),
+                    ));
+                }
+                let mut content = Vec::new();
+                stdin.read_to_end(&mut content).map_err(error)?;
+                Ok(content)
+            }
+            #[cfg(unix)]
+            Input::Fd(fd) => std::fs::read(format!("/dev/fd/{fd}")).map_err(error),
+            #[cfg(not(unix))]
+            Input::Fd(_) => Err(Error::Parse("--fd is only supported on Unix".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        let path = Input::Path(PathBuf::from("some/where/data.bin"));
+        assert_eq!(path.name(), "some/where/data.bin");
+        assert_eq!(path.short_name(), "data.bin");
+        assert_eq!(Input::Stdin.short_name(), "stdin");
+        assert_eq!(Input::Fd(3).name(), "fd 3");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reads_paths_and_descriptors() {
+        use std::os::fd::AsRawFd;
+
+        let path = std::env::temp_dir().join(format!("hex-editor-input-{}", std::process::id()));
+        std::fs::write(&path, b"\xde\xad").unwrap();
+        assert_eq!(Input::Path(path.clone()).read().unwrap(), b"\xde\xad");
+        let file = std::fs::File::open(&path).unwrap();
+        let fd = Input::Fd(file.as_raw_fd() as usize);
+        assert_eq!(fd.read().unwrap(), b"\xde\xad");
+        std::fs::remove_file(&path).unwrap();
+
+        let err = Input::Path(path).read().unwrap_err().to_string();
+        assert!(err.starts_with("cannot open '"), "{err}");
+
+        let dir = std::env::temp_dir();
+        let err = Input::Path(dir.clone()).read().unwrap_err();
+        let kind = match &err {
+            Error::Open { source, .. } => Some(source.kind()),
+            _ => None,
+        };
+        assert_eq!(kind, Some(std::io::ErrorKind::IsADirectory));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "cannot open '{}': it's a directory, not a file",
+                dir.display()
+            )
+        );
+    }
+}
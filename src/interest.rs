@@ -0,0 +1,185 @@
+//! The offsets `]` and `[` stop at: matches of the last search and the
+//! starts of runs of bytes that differ from the baseline.
+
+use crate::error::{Error, Result};
+use crate::search;
+
+/// Which offsets count as interesting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Kinds {
+    pub matches: bool,
+    pub changes: bool,
+}
+
+impl Default for Kinds {
+    fn default() -> Self {
+        Kinds {
+            matches: true,
+            changes: true,
+        }
+    }
+}
+
+impl Kinds {
+    pub const NAMES: &'static str = "matches, changes or all";
+
+    pub fn parse(text: &str) -> Result<Kinds> {
+        let (matches, changes) = match text {
+            "all" => (true, true),
+            "matches" => (true, false),
+            "changes" => (false, true),
+            _ => {
+                return Err(Error::Parse(format!(
+                    "stops must be {}, not '{text}'",
+                    Kinds::NAMES
+                )))
+            }
+        };
+        Ok(Kinds { matches, changes })
+    }
+
+    pub fn describe(self) -> &'static str {
+        match (self.matches, self.changes) {
+            (true, true) => "matches and changes",
+            (true, false) => "matches",
+            _ => "changes",
+        }
+    }
+}
+
+/// Where to look for interesting offsets
+pub struct Sources<'a> {
+    pub content: &'a [u8],
+    /// What the last search looked for
+    pub pattern: Option<&'a [u8]>,
+    pub baseline: Option<&'a [u8]>,
+    pub kinds: Kinds,
+}
+
+impl Sources<'_> {
+    /// Whether there's anything for `kinds` to stop at at all
+    pub fn is_empty(&self) -> bool {
+        let matches = self.kinds.matches && self.pattern.is_some();
+        let changes = self.kinds.changes && self.baseline.is_some();
+        !matches && !changes
+    }
+
+    /// The nearest interesting offset after `from`, or before it going
+    /// backwards, starting over from the other end of the data if needed
+    pub fn next(&self, from: usize, forward: bool) -> Option<usize> {
+        let len = self.content.len();
+        let pattern = self.pattern.filter(|_| self.kinds.matches);
+        let found_match = pattern.and_then(|pattern| match forward {
+            true => search::find(self.content, pattern, from),
+            false => search::find_back(self.content, pattern, from),
+        });
+        let baseline = self.baseline.filter(|_| self.kinds.changes);
+        let found_change =
+            baseline.and_then(|baseline| next_change(self.content, baseline, from, forward));
+        // How far each one is in the direction of travel, the offset itself
+        // coming last
+        let distance = |offset: usize| {
+            let distance = match forward {
+                true => (offset + len - from) % len,
+                false => (from + len - offset) % len,
+            };
+            if distance == 0 {
+                len
+            } else {
+                distance
+            }
+        };
+        [found_match, found_change]
+            .into_iter()
+            .flatten()
+            .min_by_key(|&offset| distance(offset))
+    }
+}
+
+/// The nearest start of a run of bytes that differ from `baseline`, after
+/// `from` or before it, starting over from the other end if needed
+fn next_change(content: &[u8], baseline: &[u8], from: usize, forward: bool) -> Option<usize> {
+    let differs = |offset: usize| baseline.get(offset) != Some(&content[offset]);
+    let starts_run = |&offset: &usize| differs(offset) && (offset == 0 || !differs(offset - 1));
+    let len = content.len();
+    if len == 0 {
+        return None;
+    }
+    let from = from.min(len - 1);
+    if forward {
+        (from + 1..len).chain(0..=from).find(starts_run)
+    } else {
+        (0..from).rev().chain((from..len).rev()).find(starts_run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources<'a>(content: &'a [u8], pattern: &'a [u8], baseline: &'a [u8]) -> Sources<'a> {
+        Sources {
+            content,
+            pattern: Some(pattern),
+            baseline: Some(baseline),
+            kinds: Kinds::default(),
+        }
+    }
+
+    #[test]
+    fn changes_are_runs() {
+        let content = b"aXXaaYa";
+        let baseline = b"aaaaaa";
+        assert_eq!(next_change(content, baseline, 0, true), Some(1));
+        assert_eq!(next_change(content, baseline, 1, true), Some(5));
+        // Bytes past the end of the baseline count as changed
+        assert_eq!(next_change(content, baseline, 5, true), Some(1));
+        assert_eq!(next_change(content, b"aXXaaYaa", 5, true), None);
+        assert_eq!(next_change(content, baseline, 5, false), Some(1));
+        assert_eq!(next_change(content, baseline, 1, false), Some(5));
+    }
+
+    #[test]
+    fn merges_matches_and_changes() {
+        let content = b"abcXbcabc";
+        let baseline = b"abcabcabc";
+        let all = sources(content, b"bc", baseline);
+        let stops: Vec<usize> = std::iter::successors(Some(0), |&from| all.next(from, true))
+            .skip(1)
+            .take(5)
+            .collect();
+        assert_eq!(stops, [1, 3, 4, 7, 1]);
+        assert_eq!(all.next(4, false), Some(3));
+        assert_eq!(all.next(1, false), Some(7));
+
+        let matches = Sources {
+            kinds: Kinds::parse("matches").unwrap(),
+            ..sources(content, b"bc", baseline)
+        };
+        assert_eq!(matches.next(1, true), Some(4));
+        let changes = Sources {
+            kinds: Kinds::parse("changes").unwrap(),
+            ..sources(content, b"bc", baseline)
+        };
+        assert_eq!(changes.next(3, true), Some(3));
+        assert!(Kinds::parse("marks").is_err());
+    }
+
+    #[test]
+    fn nothing_to_stop_at() {
+        let none = Sources {
+            content: b"abc",
+            pattern: None,
+            baseline: None,
+            kinds: Kinds::default(),
+        };
+        assert!(none.is_empty());
+        assert_eq!(none.next(0, true), None);
+        let changes_only = Sources {
+            kinds: Kinds::parse("changes").unwrap(),
+            ..sources(b"abc", b"b", b"abc")
+        };
+        assert!(!changes_only.is_empty());
+        assert_eq!(changes_only.next(0, true), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Which key does what. Every binding maps onto an `Action`, so keys that do
+//! the same thing (like `j` and Down) behave the same in every respect.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    /// Drops back into the shell, only on Unix
+    Suspend,
+    Left,
+    Down,
+    Up,
+    Right,
+    LineStart,
+    LineEnd,
+    /// The first byte of the file
+    Top,
+    /// The last byte of the file, or the start of line N with a count
+    Bottom,
+    /// Opens the `/` search prompt
+    Search,
+    /// The next match of the last search or change from the baseline
+    NextInterest,
+    PreviousInterest,
+    SwitchPane,
+    ToggleHeat,
+    DecodeFields,
+    NextEncoding,
+    /// Draws the two nibbles of each byte apart
+    ToggleNibbles,
+    /// One byte fewer per line, or N with a count
+    Narrower,
+    Wider,
+    ToggleFolds,
+    OpenFold,
+    /// Opens the `:` command line
+    CommandLine,
+    Help,
+}
+
+/// The groups the help screen lists the bindings in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Category {
+    Movement,
+    View,
+    General,
+}
+
+impl Category {
+    pub const ALL: [Category; 3] = [Category::Movement, Category::View, Category::General];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Movement => "Movement",
+            Category::View => "View",
+            Category::General => "General",
+        }
+    }
+}
+
+impl Action {
+    pub fn category(self) -> Category {
+        match self {
+            Action::Left
+            | Action::Down
+            | Action::Up
+            | Action::Right
+            | Action::LineStart
+            | Action::LineEnd
+            | Action::Top
+            | Action::Bottom
+            | Action::Search
+            | Action::NextInterest
+            | Action::PreviousInterest => Category::Movement,
+            Action::SwitchPane
+            | Action::ToggleHeat
+            | Action::DecodeFields
+            | Action::NextEncoding
+            | Action::ToggleNibbles
+            | Action::Narrower
+            | Action::Wider
+            | Action::ToggleFolds
+            | Action::OpenFold => Category::View,
+            Action::Quit | Action::Suspend | Action::CommandLine | Action::Help => {
+                Category::General
+            }
+        }
+    }
+
+    /// What the help screen says the action does
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Suspend => "suspend to the shell",
+            Action::Left => "left one nibble or byte",
+            Action::Down => "down one line",
+            Action::Up => "up one line",
+            Action::Right => "right one nibble or byte",
+            Action::LineStart => "start of the line",
+            Action::LineEnd => "end of the line",
+            Action::Top => "start of the file",
+            Action::Bottom => "end of the file, or line N with a count",
+            Action::Search => "search, for hex in the hex pane and text in the ASCII pane",
+            Action::NextInterest => "next search match or change from --baseline",
+            Action::PreviousInterest => "previous search match or change from --baseline",
+            Action::SwitchPane => "switch between the hex and ASCII panes",
+            Action::ToggleHeat => "show or hide the byte-frequency heat map",
+            Action::DecodeFields => "decode the bytes at the cursor with --struct",
+            Action::NextEncoding => "decode a string at the cursor, again for the next encoding",
+            Action::ToggleNibbles => "show the nibbles of each byte apart, d e instead of de",
+            Action::Narrower => "one byte fewer per line, or N fewer with a count",
+            Action::Wider => "one byte more per line, or N more with a count",
+            Action::ToggleFolds => "fold long runs of one byte, or unfold everything",
+            Action::OpenFold => "unfold the run at the cursor",
+            Action::CommandLine => "command line (:goto, :cols, :help, :q)",
+            Action::Help => "this help",
+        }
+    }
+}
+
+const NONE: KeyModifiers = KeyModifiers::NONE;
+const CONTROL: KeyModifiers = KeyModifiers::CONTROL;
+
+/// Every key binding, in the order they're listed to the user
+pub const BINDINGS: &[(KeyCode, KeyModifiers, Action)] = &[
+    (KeyCode::Char('q'), NONE, Action::Quit),
+    // Raw mode delivers Ctrl-C as a key instead of a signal
+    (KeyCode::Char('c'), CONTROL, Action::Quit),
+    (KeyCode::Char('z'), CONTROL, Action::Suspend),
+    (KeyCode::Char('h'), NONE, Action::Left),
+    (KeyCode::Left, NONE, Action::Left),
+    (KeyCode::Char('j'), NONE, Action::Down),
+    (KeyCode::Down, NONE, Action::Down),
+    (KeyCode::Enter, NONE, Action::Down),
+    (KeyCode::Char('k'), NONE, Action::Up),
+    (KeyCode::Up, NONE, Action::Up),
+    (KeyCode::Char('l'), NONE, Action::Right),
+    (KeyCode::Right, NONE, Action::Right),
+    (KeyCode::Char('0'), NONE, Action::LineStart),
+    (KeyCode::Home, NONE, Action::LineStart),
+    (KeyCode::Char('$'), NONE, Action::LineEnd),
+    (KeyCode::End, NONE, Action::LineEnd),
+    (KeyCode::Char('g'), NONE, Action::Top),
+    (KeyCode::Home, CONTROL, Action::Top),
+    (KeyCode::Char('G'), NONE, Action::Bottom),
+    (KeyCode::End, CONTROL, Action::Bottom),
+    (KeyCode::Char('/'), NONE, Action::Search),
+    (KeyCode::Char(']'), NONE, Action::NextInterest),
+    (KeyCode::Char('['), NONE, Action::PreviousInterest),
+    (KeyCode::Tab, NONE, Action::SwitchPane),
+    (KeyCode::Char('F'), NONE, Action::ToggleHeat),
+    (KeyCode::Char('S'), NONE, Action::DecodeFields),
+    (KeyCode::Char('e'), NONE, Action::NextEncoding),
+    (KeyCode::Char('x'), NONE, Action::ToggleNibbles),
+    (KeyCode::Char('<'), NONE, Action::Narrower),
+    (KeyCode::Char('>'), NONE, Action::Wider),
+    (KeyCode::Char('z'), NONE, Action::ToggleFolds),
+    (KeyCode::Char('o'), NONE, Action::OpenFold),
+    (KeyCode::Char(':'), NONE, Action::CommandLine),
+    (KeyCode::Char('?'), NONE, Action::Help),
+];
+
+/// The action bound to `key`, if any
+pub fn action_for(key: &KeyEvent) -> Option<Action> {
+    let mut modifiers = key.modifiers;
+    // Terminals disagree on whether capitals and symbols come with Shift,
+    // the character already says it all
+    if let KeyCode::Char(_) = key.code {
+        modifiers.remove(KeyModifiers::SHIFT);
+    }
+    BINDINGS
+        .iter()
+        .find(|(code, mods, _)| *code == key.code && *mods == modifiers)
+        .map(|&(_, _, action)| action)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(code: KeyCode, modifiers: KeyModifiers) -> Option<Action> {
+        action_for(&KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn letters_and_keys_agree() {
+        assert_eq!(action(KeyCode::Char('j'), NONE), Some(Action::Down));
+        assert_eq!(action(KeyCode::Down, NONE), Some(Action::Down));
+        assert_eq!(action(KeyCode::Enter, NONE), Some(Action::Down));
+        assert_eq!(action(KeyCode::Home, NONE), Some(Action::LineStart));
+        assert_eq!(action(KeyCode::Home, CONTROL), Some(Action::Top));
+        assert_eq!(action(KeyCode::End, CONTROL), Some(Action::Bottom));
+    }
+
+    #[test]
+    fn shift_is_ignored_on_characters_only() {
+        let shift = KeyModifiers::SHIFT;
+        assert_eq!(action(KeyCode::Char('G'), shift), Some(Action::Bottom));
+        assert_eq!(action(KeyCode::Char('$'), shift), Some(Action::LineEnd));
+        assert_eq!(action(KeyCode::Home, shift), None);
+        assert_eq!(action(KeyCode::Char('c'), NONE), None);
+        assert_eq!(action(KeyCode::Char('c'), CONTROL), Some(Action::Quit));
+        assert_eq!(action(KeyCode::Char('j'), KeyModifiers::ALT), None);
+    }
+
+    #[test]
+    fn keys_are_bound_once() {
+        for (i, (code, mods, _)) in BINDINGS.iter().enumerate() {
+            let twice = BINDINGS[i + 1..]
+                .iter()
+                .any(|(other, other_mods, _)| other == code && other_mods == mods);
+            assert!(!twice, "{code:?} {mods:?}");
+        }
+    }
+}
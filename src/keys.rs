@@ -0,0 +1,199 @@
+//! Scripted key presses, written in vim-like notation: plain characters for
+//! themselves and `<...>` for everything else, e.g. `jjl<C-d>:goto 10<CR>`.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+use crate::backend::EventSource;
+use crate::error::{Error, Result};
+
+/// Parses a key script. Line breaks are skipped so longer scripts can be
+/// split over several lines, use `<CR>` for Enter. `<lt>` is a literal `<`.
+///
+/// Between the angle brackets go any of `C-`, `S-` and `A-` (or `M-`) for
+/// Control, Shift and Alt, followed by a single character or one of `CR`,
+/// `Enter`, `Esc`, `Tab`, `BS`, `Space`, `Del`, `Up`, `Down`, `Left`,
+/// `Right`, `Home`, `End`, `PageUp`, `PageDown`, `F1` to `F12`.
+pub fn parse_keys(text: &str) -> Result<Vec<KeyEvent>> {
+    let mut keys = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '\n' | '\r' => {}
+            '<' => {
+                let (name, tail) = rest
+                    .split_once('>')
+                    .ok_or_else(|| Error::Parse(format!("unclosed '<' in keys: <{rest}")))?;
+                keys.push(parse_special(name)?);
+                rest = tail;
+            }
+            c => keys.push(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE)),
+        }
+    }
+    Ok(keys)
+}
+
+/// Parses what's between the angle brackets of `<...>`
+fn parse_special(name: &str) -> Result<KeyEvent> {
+    let error = || Error::Parse(format!("unknown key <{name}>"));
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = name;
+    // A trailing "-" is the key itself, as in <C-->
+    while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+        modifiers |= match rest.as_bytes()[0].to_ascii_uppercase() {
+            b'C' => KeyModifiers::CONTROL,
+            b'S' => KeyModifiers::SHIFT,
+            b'A' | b'M' => KeyModifiers::ALT,
+            _ => return Err(error()),
+        };
+        rest = &rest[2..];
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) if modifiers != KeyModifiers::NONE => KeyCode::Char(c),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "lt" => KeyCode::Char('<'),
+            "cr" | "enter" | "return" => KeyCode::Enter,
+            "esc" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "bs" | "backspace" => KeyCode::Backspace,
+            "space" => KeyCode::Char(' '),
+            "del" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            f => match f.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                Some(n @ 1..=12) => KeyCode::F(n),
+                _ => return Err(error()),
+            },
+        },
+    };
+    Ok(KeyEvent::new(code, modifiers))
+}
+
+/// Writes a key the way `parse_keys` reads it, e.g. `q`, `<C-c>` or `<Left>`
+pub fn key_name(code: KeyCode, modifiers: KeyModifiers) -> String {
+    let name = match code {
+        KeyCode::Char('<') => "lt".to_string(),
+        KeyCode::Char(' ') => "Space".to_string(),
+        KeyCode::Char(c) if modifiers.is_empty() => return c.to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "CR".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::Backspace => "BS".to_string(),
+        KeyCode::Delete => "Del".to_string(),
+        KeyCode::F(n) => format!("F{n}"),
+        // Up, Down, Home, PageUp and the rest are spelled like the variants
+        other => format!("{other:?}"),
+    };
+    let mut text = String::from("<");
+    for (modifier, prefix) in [
+        (KeyModifiers::CONTROL, "C-"),
+        (KeyModifiers::SHIFT, "S-"),
+        (KeyModifiers::ALT, "A-"),
+    ] {
+        if modifiers.contains(modifier) {
+            text.push_str(prefix);
+        }
+    }
+    text.push_str(&name);
+    text.push('>');
+    text
+}
+
+/// Hands out a script of key presses before passing on the events of
+/// `inner`, so replayed keys go through exactly the same path as typed ones.
+pub struct Replay<E> {
+    keys: VecDeque<KeyEvent>,
+    inner: E,
+}
+
+impl<E: EventSource> Replay<E> {
+    pub fn new(keys: Vec<KeyEvent>, inner: E) -> Self {
+        Replay {
+            keys: keys.into(),
+            inner,
+        }
+    }
+}
+
+impl<E: EventSource> EventSource for Replay<E> {
+    fn next_event(&mut self, timeout: Duration) -> std::io::Result<Option<Event>> {
+        match self.keys.pop_front() {
+            Some(key) => Ok(Some(Event::Key(key))),
+            None => self.inner.next_event(timeout),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn plain_characters() {
+        let keys = parse_keys("jj\nl:q").unwrap();
+        let expected: Vec<KeyEvent> = "jjl:q".chars().map(|c| key(KeyCode::Char(c))).collect();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn special_keys() {
+        let keys = parse_keys("<CR><esc><Tab><lt><PageDown><F5>").unwrap();
+        assert_eq!(
+            keys,
+            [
+                key(KeyCode::Enter),
+                key(KeyCode::Esc),
+                key(KeyCode::Tab),
+                key(KeyCode::Char('<')),
+                key(KeyCode::PageDown),
+                key(KeyCode::F(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn modifiers() {
+        let keys = parse_keys("<C-c><C-S-Home><A-->").unwrap();
+        assert_eq!(
+            keys,
+            [
+                KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
+                KeyEvent::new(KeyCode::Home, KeyModifiers::CONTROL | KeyModifiers::SHIFT),
+                KeyEvent::new(KeyCode::Char('-'), KeyModifiers::ALT),
+            ]
+        );
+    }
+
+    #[test]
+    fn names_parse_back() {
+        let keys = parse_keys("q<lt>$<Space><CR><Left><PageUp><F12><C-c><C-S-Home><A-->").unwrap();
+        let names: String = keys.iter().map(|k| key_name(k.code, k.modifiers)).collect();
+        assert_eq!(
+            names,
+            "q<lt>$<Space><CR><Left><PageUp><F12><C-c><C-S-Home><A-->"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        for bad in ["<CR", "<nope>", "<F13>", "<X-a>", "<>"] {
+            assert!(parse_keys(bad).is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! Where each part of a line is drawn. Rendering, cursor placement and mouse
+//! hit-testing all go through here so they agree on every column.
+
+use crate::editor::Pane;
+
+/// Width of the "00000000: " offset column
+pub const OFFSET_WIDTH: usize = 10;
+
+/// Width of the "  00000000" offset repeated after the ASCII pane
+pub const RIGHT_OFFSET_WIDTH: usize = 10;
+
+/// Which cell of a line a screen column falls on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hit {
+    pub pane: Pane,
+    /// Index of the byte within the line
+    pub index: usize,
+    /// 0 for the high nibble, 1 for the low one. Always 0 in the ASCII pane.
+    pub nibble: usize,
+}
+
+/// Byte indices are always within the whole line. On screens too narrow for
+/// a full line only `first..first + visible` of them are drawn, and the
+/// column methods are only meaningful for those.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layout {
+    /// Bytes per line
+    pub line_length: usize,
+    /// First byte of each line that's drawn
+    pub first: usize,
+    /// Number of bytes drawn per line
+    pub visible: usize,
+    /// Whether there's room for the ASCII pane
+    pub ascii: bool,
+    /// Whether the nibbles of each byte are drawn apart, `d e` rather than
+    /// `de`
+    pub nibbles: bool,
+    /// Whether the offset is repeated after the ASCII pane. Only drawn
+    /// along with the ASCII pane.
+    pub right_offset: bool,
+    /// Whether the ASCII pane goes before the hex pane instead of after it
+    pub ascii_left: bool,
+}
+
+impl Layout {
+    /// A layout with the whole line drawn
+    pub fn new(line_length: usize) -> Self {
+        Layout {
+            line_length,
+            first: 0,
+            visible: line_length,
+            ascii: true,
+            nibbles: false,
+            right_offset: false,
+            ascii_left: false,
+        }
+    }
+
+    /// This layout, which has the whole line drawn, cut down for a screen
+    /// `columns` wide. The ASCII pane goes first, then bytes at the end of
+    /// the line, starting from `first` if possible. Cells are never cut in
+    /// half. The offset on the right goes before anything else.
+    pub fn fit(self, columns: usize, first: usize) -> Self {
+        if columns >= self.width() {
+            return self;
+        }
+        let without_right_offset = Layout {
+            right_offset: false,
+            ..self
+        };
+        if columns >= without_right_offset.width() {
+            return without_right_offset;
+        }
+        // The last cell doesn't need the space after it
+        let visible = (columns.saturating_sub(OFFSET_WIDTH) + 1) / self.cell_width();
+        let visible = visible.clamp(1, self.line_length);
+        Layout {
+            first: first.min(self.line_length - visible),
+            visible,
+            ascii: false,
+            right_offset: false,
+            ..self
+        }
+    }
+
+    /// Columns a byte takes in the hex pane, including the space after it
+    pub fn cell_width(&self) -> usize {
+        if self.nibbles {
+            4
+        } else {
+            3
+        }
+    }
+
+    /// Columns from a byte's high nibble to its low one
+    fn nibble_step(&self) -> usize {
+        if self.nibbles {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Columns needed to draw everything in the layout
+    pub fn width(&self) -> usize {
+        if self.ascii && self.right_offset {
+            self.panes_end() + RIGHT_OFFSET_WIDTH
+        } else {
+            self.panes_end()
+        }
+    }
+
+    /// Column after the last cell of whichever pane comes last
+    fn panes_end(&self) -> usize {
+        if self.ascii && !self.ascii_left {
+            self.ascii_column(self.first + self.visible)
+        } else {
+            // The last cell doesn't need the space after it
+            self.hex_end() - 1
+        }
+    }
+
+    pub fn is_visible(&self, index: usize) -> bool {
+        (self.first..self.first + self.visible).contains(&index)
+    }
+
+    /// Column of the high nibble of byte `index`
+    pub fn hex_column(&self, index: usize) -> usize {
+        // Two spaces apart from the ASCII pane, like the other way round
+        let start = if self.ascii && self.ascii_left {
+            OFFSET_WIDTH + self.visible + 2
+        } else {
+            OFFSET_WIDTH
+        };
+        start + (index - self.first) * self.cell_width()
+    }
+
+    /// Column of the space after the last hex cell, between the hex and the
+    /// ASCII panes unless the ASCII pane is on the left
+    pub fn hex_end(&self) -> usize {
+        self.hex_column(self.first + self.visible)
+    }
+
+    pub fn ascii_column(&self, index: usize) -> usize {
+        if self.ascii_left {
+            OFFSET_WIDTH + index - self.first
+        } else {
+            self.hex_end() + 1 + index - self.first
+        }
+    }
+
+    /// Column the repeated offset starts in
+    pub fn right_offset_column(&self) -> usize {
+        self.panes_end() + 2
+    }
+
+    /// Column the cursor is drawn in for a byte (and nibble in the hex pane).
+    /// With the ASCII pane hidden it's drawn on the byte's hex cell instead.
+    pub fn cursor_column(&self, pane: Pane, index: usize, nibble: usize) -> usize {
+        match pane {
+            Pane::Ascii if self.ascii => self.ascii_column(index),
+            Pane::Ascii => self.hex_column(index),
+            Pane::Hex => self.hex_column(index) + nibble * self.nibble_step(),
+        }
+    }
+
+    /// The byte cell drawn at `col`, or `None` for the offset column and the
+    /// gaps between cells
+    pub fn hit(&self, col: usize) -> Option<Hit> {
+        let ascii_start = self.ascii_column(self.first);
+        if self.ascii && (ascii_start..ascii_start + self.visible).contains(&col) {
+            return Some(Hit {
+                pane: Pane::Ascii,
+                index: self.first + col - ascii_start,
+                nibble: 0,
+            });
+        }
+        let hex_start = self.hex_column(self.first);
+        if col < hex_start || col >= self.hex_end() {
+            return None;
+        }
+        let index = self.first + (col - hex_start) / self.cell_width();
+        let step = self.nibble_step();
+        let column = col - self.hex_column(index);
+        (column.is_multiple_of(step) && column / step < 2).then_some(Hit {
+            pane: Pane::Hex,
+            index,
+            nibble: column / step,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn columns() {
+        let layout = Layout::new(16);
+        assert_eq!(layout.hex_column(0), 10);
+        assert_eq!(layout.hex_column(15), 55);
+        assert_eq!(layout.ascii_column(0), 59);
+        assert_eq!(layout.ascii_column(15), 74);
+        assert_eq!(layout.cursor_column(Pane::Hex, 1, 1), 14);
+        assert_eq!(layout.cursor_column(Pane::Ascii, 1, 1), 60);
+    }
+
+    #[test]
+    fn hit_every_cursor_column() {
+        let layout = Layout::new(16);
+        for index in 0..16 {
+            for nibble in 0..2 {
+                let col = layout.cursor_column(Pane::Hex, index, nibble);
+                let hit = Hit {
+                    pane: Pane::Hex,
+                    index,
+                    nibble,
+                };
+                assert_eq!(layout.hit(col), Some(hit));
+            }
+            let col = layout.cursor_column(Pane::Ascii, index, 0);
+            let hit = Hit {
+                pane: Pane::Ascii,
+                index,
+                nibble: 0,
+            };
+            assert_eq!(layout.hit(col), Some(hit));
+        }
+    }
+
+    #[test]
+    fn fit_keeps_full_line_when_it_fits() {
+        assert_eq!(Layout::new(16).width(), 75);
+        assert_eq!(Layout::new(16).fit(75, 3), Layout::new(16));
+        assert_eq!(Layout::new(16).fit(200, 0), Layout::new(16));
+    }
+
+    #[test]
+    fn fit_drops_ascii_then_bytes() {
+        let layout = Layout::new(16).fit(74, 0);
+        assert!(!layout.ascii);
+        assert_eq!(layout.visible, 16);
+        assert_eq!(layout.width(), 57);
+
+        // 10 columns of offset leave room for 3 whole cells and a bit
+        let layout = Layout::new(16).fit(20, 0);
+        assert_eq!(layout.visible, 3);
+        assert_eq!(layout.width(), 18);
+        assert_eq!(Layout::new(16).fit(21, 0).visible, 4);
+    }
+
+    #[test]
+    fn fit_scrolls_without_cutting_cells() {
+        for columns in 12..75 {
+            for first in 0..16 {
+                let layout = Layout::new(16).fit(columns, first);
+                assert!(layout.width() <= columns, "{columns} columns");
+                assert!(layout.first + layout.visible <= 16);
+                assert_eq!(layout.hex_column(layout.first), OFFSET_WIDTH);
+            }
+        }
+        let layout = Layout::new(16).fit(40, 12);
+        assert_eq!((layout.first, layout.visible), (6, 10));
+        assert!(layout.is_visible(15) && !layout.is_visible(5));
+    }
+
+    #[test]
+    fn hit_scrolled_layout() {
+        let layout = Layout::new(16).fit(40, 6);
+        assert_eq!(
+            layout.hit(OFFSET_WIDTH + 4),
+            Some(Hit {
+                pane: Pane::Hex,
+                index: 7,
+                nibble: 1,
+            })
+        );
+        assert_eq!(layout.hit(layout.hex_end()), None);
+        assert_eq!(layout.cursor_column(Pane::Ascii, 7, 0), OFFSET_WIDTH + 3);
+    }
+
+    #[test]
+    fn nibbles_apart() {
+        let layout = Layout {
+            nibbles: true,
+            ..Layout::new(16)
+        };
+        assert_eq!(layout.cursor_column(Pane::Hex, 1, 1), 16);
+        assert_eq!(layout.ascii_column(0), 75);
+        assert_eq!(layout.width(), 91);
+        for index in 0..16 {
+            for nibble in 0..2 {
+                let col = layout.cursor_column(Pane::Hex, index, nibble);
+                let hit = layout.hit(col).unwrap();
+                assert_eq!((hit.index, hit.nibble), (index, nibble));
+                // The spaces between the nibbles and after the byte
+                assert_eq!(layout.hit(col + 1), None);
+            }
+        }
+        // After the offset, 20 columns are one short of "d e d e d e"
+        let narrow = layout.fit(20, 0);
+        assert_eq!((narrow.visible, narrow.width()), (2, 17));
+        assert_eq!(layout.fit(21, 0).visible, 3);
+    }
+
+    #[test]
+    fn right_offset_goes_first() {
+        let layout = Layout {
+            right_offset: true,
+            ..Layout::new(16)
+        };
+        assert_eq!(layout.right_offset_column(), 77);
+        assert_eq!(layout.width(), 85);
+        assert_eq!(layout.fit(85, 0), layout);
+        assert_eq!(layout.fit(84, 0), Layout::new(16));
+        assert!(!layout.fit(74, 0).right_offset);
+        assert_eq!(layout.hit(80), None);
+    }
+
+    #[test]
+    fn ascii_on_the_left() {
+        let layout = Layout {
+            ascii_left: true,
+            right_offset: true,
+            ..Layout::new(16)
+        };
+        assert_eq!(layout.ascii_column(0), 10);
+        assert_eq!(layout.hex_column(0), 28);
+        assert_eq!(layout.cursor_column(Pane::Hex, 1, 1), 32);
+        // As wide as the other way round
+        assert_eq!(layout.width(), 85);
+        assert_eq!(layout.right_offset_column(), 77);
+        for index in 0..16 {
+            for pane in [Pane::Hex, Pane::Ascii] {
+                let col = layout.cursor_column(pane, index, 0);
+                assert_eq!(
+                    layout.hit(col).map(|hit| (hit.pane, hit.index)),
+                    Some((pane, index))
+                );
+            }
+        }
+        for col in [9, 26, 27, 76] {
+            assert_eq!(layout.hit(col), None, "column {col}");
+        }
+        // Without the ASCII pane the hex goes right after the offset
+        let narrow = layout.fit(40, 0);
+        assert_eq!(narrow.hex_column(0), OFFSET_WIDTH);
+        assert_eq!(narrow.cursor_column(Pane::Ascii, 2, 0), 16);
+    }
+
+    #[test]
+    fn hit_gaps() {
+        let layout = Layout::new(16);
+        // Offset column, separator, the gap between the panes, past the end
+        for col in [0, 9, 12, 57, 58, 75, 200] {
+            assert_eq!(layout.hit(col), None, "column {col}");
+        }
+    }
+}
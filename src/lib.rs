@@ -0,0 +1,32 @@
+pub mod args;
+pub mod backend;
+pub mod base64;
+pub mod bitmap;
+pub mod command;
+pub mod confirm;
+pub mod digest;
+pub mod editor;
+pub mod error;
+pub mod fake;
+pub mod fields;
+pub mod filter;
+pub mod fold;
+pub mod heat;
+pub mod help;
+pub mod history;
+pub mod input;
+pub mod interest;
+pub mod keymap;
+pub mod keys;
+pub mod layout;
+pub mod line;
+pub mod logging;
+pub mod message;
+pub mod printable;
+pub mod screenshot;
+pub mod search;
+pub mod status;
+pub mod strings;
+pub mod terminal;
+pub mod time;
+pub mod viewport;
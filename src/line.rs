@@ -0,0 +1,173 @@
+//! The text of a prompt and the cursor in it, edited the same way on every
+//! prompt.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LineEdit {
+    text: String,
+    /// Byte index of the character the cursor is on, `text.len()` past the
+    /// end
+    cursor: usize,
+}
+
+impl LineEdit {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text, with the cursor after it
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    /// Reacts to `key` if it's one for editing the line. Returns whether the
+    /// text changed, or `None` for keys that aren't about the line.
+    pub fn key(&mut self, key: &KeyEvent) -> Option<bool> {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        let old_len = self.text.len();
+        match key.code {
+            KeyCode::Left => self.cursor = self.before(self.cursor),
+            KeyCode::Right => self.cursor = self.after(self.cursor),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.len(),
+            KeyCode::Backspace => {
+                let start = self.before(self.cursor);
+                self.remove(start..self.cursor);
+            }
+            KeyCode::Delete => self.remove(self.cursor..self.after(self.cursor)),
+            KeyCode::Char('u') if control => self.remove(0..self.cursor),
+            KeyCode::Char('w') if control => self.remove(self.word_start()..self.cursor),
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.text.insert(self.cursor, c);
+                self.cursor += c.len_utf8();
+            }
+            _ => return None,
+        }
+        Some(self.text.len() != old_len)
+    }
+
+    /// The part of the text that fits in `width` columns with the cursor on
+    /// it, and the column of the cursor in that. The text scrolls once the
+    /// cursor gets to the last column.
+    pub fn view(&self, width: usize) -> (String, usize) {
+        let cursor = self.text[..self.cursor].chars().count();
+        let first = (cursor + 1).saturating_sub(width.max(1));
+        let shown = self.text.chars().skip(first).take(width).collect();
+        (shown, cursor - first)
+    }
+
+    fn remove(&mut self, range: std::ops::Range<usize>) {
+        self.cursor = range.start;
+        self.text.replace_range(range, "");
+    }
+
+    fn before(&self, index: usize) -> usize {
+        self.text[..index]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(i, _)| i)
+    }
+
+    fn after(&self, index: usize) -> usize {
+        self.text[index..]
+            .chars()
+            .next()
+            .map_or(index, |c| index + c.len_utf8())
+    }
+
+    /// Where the word before the cursor starts, past any spaces after it,
+    /// for Ctrl-W
+    fn word_start(&self) -> usize {
+        self.text[..self.cursor]
+            .trim_end()
+            .char_indices()
+            .rfind(|(_, c)| c.is_whitespace())
+            .map_or(0, |(i, c)| i + c.len_utf8())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(line: &mut LineEdit, code: KeyCode) -> Option<bool> {
+        line.key(&KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn control(line: &mut LineEdit, c: char) -> Option<bool> {
+        line.key(&KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL))
+    }
+
+    fn typed(text: &str) -> LineEdit {
+        let mut line = LineEdit::default();
+        for c in text.chars() {
+            press(&mut line, KeyCode::Char(c));
+        }
+        line
+    }
+
+    #[test]
+    fn edits_anywhere_on_the_line() {
+        let mut line = typed("goto 10");
+        assert_eq!(press(&mut line, KeyCode::Left), Some(false));
+        assert_eq!(press(&mut line, KeyCode::Left), Some(false));
+        assert_eq!(press(&mut line, KeyCode::Char('x')), Some(true));
+        assert_eq!(line.text(), "goto x10");
+        press(&mut line, KeyCode::Backspace);
+        press(&mut line, KeyCode::Delete);
+        assert_eq!(line.text(), "goto 0");
+        press(&mut line, KeyCode::Home);
+        assert_eq!(press(&mut line, KeyCode::Backspace), Some(false));
+        press(&mut line, KeyCode::Delete);
+        press(&mut line, KeyCode::End);
+        press(&mut line, KeyCode::Char('0'));
+        assert_eq!(line.text(), "oto 00");
+        assert_eq!(press(&mut line, KeyCode::Right), Some(false));
+        assert_eq!(press(&mut line, KeyCode::Up), None);
+        assert_eq!(control(&mut line, 'c'), None);
+    }
+
+    #[test]
+    fn deletes_words_and_to_the_start() {
+        let mut line = typed("cols  16  ");
+        assert_eq!(control(&mut line, 'w'), Some(true));
+        assert_eq!(line.text(), "cols  ");
+        control(&mut line, 'w');
+        assert_eq!(line.text(), "");
+        assert_eq!(control(&mut line, 'w'), Some(false));
+
+        let mut line = typed("goto 0x100");
+        press(&mut line, KeyCode::Left);
+        control(&mut line, 'u');
+        assert_eq!(line.text(), "0");
+        assert_eq!(line.view(10), ("0".to_string(), 0));
+    }
+
+    #[test]
+    fn handles_multibyte_characters() {
+        let mut line = typed("añb");
+        press(&mut line, KeyCode::Left);
+        press(&mut line, KeyCode::Backspace);
+        assert_eq!(line.text(), "ab");
+        let mut line = typed("a ñé");
+        control(&mut line, 'w');
+        assert_eq!(line.text(), "a ");
+    }
+
+    #[test]
+    fn scrolls_to_the_cursor() {
+        let mut line = LineEdit::default();
+        line.set_text("0123456789");
+        assert_eq!(line.view(20), ("0123456789".to_string(), 10));
+        assert_eq!(line.view(4), ("789".to_string(), 3));
+        press(&mut line, KeyCode::Home);
+        assert_eq!(line.view(4), ("0123".to_string(), 0));
+        assert_eq!(line.view(0), (String::new(), 0));
+    }
+}
@@ -0,0 +1,54 @@
+//! Debug log written to a file, since a TUI can't print while it runs.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        if let Ok(mut file) = self.file.lock() {
+            // A log line that can't be written isn't worth interrupting the user for
+            let _ = writeln!(
+                file,
+                "{}.{:03} {:<5} {}",
+                now.as_secs(),
+                now.subsec_millis(),
+                record.level(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Appends every log record to the file at `path`. Without this nothing is
+/// logged at all.
+pub fn init(path: &Path) -> std::io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let logger = FileLogger {
+        file: Mutex::new(file),
+    };
+    log::set_boxed_logger(Box::new(logger)).map_err(std::io::Error::other)?;
+    log::set_max_level(LevelFilter::Trace);
+    Ok(())
+}
@@ -1,219 +1,168 @@
-use std::io::stdout;
-
-use crossterm::cursor::{RestorePosition, SavePosition};
-use crossterm::terminal::window_size;
-use crossterm::{
-    cursor, cursor::MoveTo, event::KeyCode::Char, style::Print, terminal::enable_raw_mode,
-    ExecutableCommand,
-};
-
-use crossterm::event::{read, EnableMouseCapture, Event, MouseEvent, MouseEventKind};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use hex_editor::args::Args;
+use hex_editor::backend::{CrosstermBackend, CrosstermEvents};
+use hex_editor::editor::{Editor, Outcome, RULER_INTERVAL};
+use hex_editor::error::{Error, Result};
+use hex_editor::filter;
+use hex_editor::help;
+use hex_editor::history::{self, Histories};
+use hex_editor::keys::{self, Replay};
+use hex_editor::logging;
+use hex_editor::message::Severity;
+use hex_editor::status::{self, StatusFormat};
+use hex_editor::terminal::TerminalGuard;
+
+fn main() -> ExitCode {
+    // The terminal has been restored by the time run returns, so this is visible
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("hex-editor: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
 
-const LINE_LENGTH: usize = 16;
+fn run() -> Result<()> {
+    let args = Args::parse(std::env::args_os().skip(1))?;
+    if args.help_keys {
+        for line in help::help_lines() {
+            println!("{line}");
+        }
+        return Ok(());
+    }
 
-enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
-}
+    if let Some(log_file) = &args.log_file {
+        logging::init(log_file).map_err(|source| Error::Open {
+            path: log_file.clone(),
+            source,
+        })?;
+    }
 
-fn draw_line(blob: &[u8], pos: usize, line_length: usize, cols: usize) -> std::io::Result<()> {
-    let bytes = blob
-        .iter()
-        .skip(pos * line_length)
-        .take(line_length)
-        .map(|&c| format!("{:02x}", c))
-        .collect::<Vec<_>>()
-        .join(" ");
-    let chars = blob
-        .iter()
-        .skip(pos * line_length)
-        .take(line_length)
-        .map(|&c| {
-            if c.is_ascii_alphanumeric() {
-                c as char
-            } else {
-                '.'
-            }
-        })
-        .collect::<String>();
-    let line_num = pos * line_length;
-
-    let line = format!(
-        "{line_num:08x}: {bytes: <width$} {chars}",
-        width = line_length * 3
-    );
-
-    let len = line.len();
-    if len < cols {
-        stdout().execute(Print(line))?;
-        for _ in 0..(cols - len) {
-            stdout().execute(Print(" "))?;
+    let input = args.input;
+    let content = input.read()?;
+    log::info!("read {} bytes from {}", content.len(), input.name());
+    let content = match &args.on_load {
+        Some(command) => {
+            let filtered = filter::run(command, &content)?;
+            log::info!("'{command}' turned them into {} bytes", filtered.len());
+            filtered
         }
-    } else {
-        let line = line[..cols].to_string();
-        stdout().execute(Print(line))?;
+        None => content,
+    };
+    let baseline = match &args.baseline {
+        Some(path) => Some(std::fs::read(path).map_err(|source| Error::Open {
+            path: path.clone(),
+            source,
+        })?),
+        None => None,
     };
-    Ok(())
-}
 
-fn move_cursor(
-    start: &mut usize,
-    total_lines: usize,
-    direction: Direction,
-) -> std::io::Result<bool> {
-    let mut curos_pos = cursor::position()?;
-    let win_size = window_size()?;
-    let rows = win_size.rows as usize;
-
-    let mut requires_redraw = false;
-
-    match direction {
-        Direction::Up => {
-            if curos_pos.1 > 0 {
-                // The cursos is anywhere but the first line
-                curos_pos.1 -= 1;
-            } else if *start > 0 {
-                // The cursos is on the first line, but there are more lines to show
-                *start -= 1;
-                requires_redraw = true;
-            }
-        }
-        Direction::Down => {
-            let max_start = if total_lines <= rows {
-                0
-            } else {
-                total_lines - rows
-            };
-            if curos_pos.1 < (rows - 2) as u16 {
-                // The cursos is anywhere but the last line
-                curos_pos.1 += 1;
-            } else if *start < max_start {
-                // The cursos is on the last line, but there are more lines to show
-                *start += 1;
-                requires_redraw = true;
-            }
-        }
-        Direction::Left => {
-            if curos_pos.0 > 10 {
-                curos_pos.0 -= 1;
-            }
-        }
-        Direction::Right => {
-            if curos_pos.0 < 10 + LINE_LENGTH as u16 * 3 {
-                curos_pos.0 += 1;
-            }
+    let keys = match &args.keys {
+        Some(path) => {
+            let script = std::fs::read_to_string(path).map_err(|source| Error::Open {
+                path: path.clone(),
+                source,
+            })?;
+            keys::parse_keys(&script)?
         }
-    }
-    stdout().execute(MoveTo(curos_pos.0, curos_pos.1))?;
-    Ok(requires_redraw)
-}
+        None => Vec::new(),
+    };
 
-fn goto_start(start: &mut usize) -> std::io::Result<bool> {
-    stdout().execute(MoveTo(10, 0))?;
-    if *start > 0 {
-        *start = 0;
-        return Ok(true);
-    }
-    Ok(false)
-}
+    let (status_format, unknown) = match &args.status {
+        Some(format) => {
+            let (format, unknown) = StatusFormat::parse(format);
+            (Some(format), unknown)
+        }
+        None => (None, Vec::new()),
+    };
 
-fn goto_end(start: &mut usize, total_lines: usize) -> std::io::Result<bool> {
-    let rows = window_size()?.rows as usize;
-    let max_start = if total_lines <= rows {
-        0
-    } else {
-        total_lines - (rows - 1)
+    // Losing the prompt history isn't worth refusing to start over, it's
+    // reported once the editor is up
+    let data_dir = history::data_dir();
+    let mut history_error = None;
+    let saved_history = match &data_dir {
+        Some(dir) => Histories::load(dir).unwrap_or_else(|err| {
+            history_error = Some(format!(
+                "cannot read the history in {}: {err}",
+                dir.display()
+            ));
+            Histories::default()
+        }),
+        None => Histories::default(),
     };
 
-    stdout().execute(MoveTo(10, rows as u16 - 2))?;
-    if *start < max_start {
-        *start = max_start;
-        return Ok(true);
+    // Set when we get asked to terminate, so we can leave the loop and restore the terminal
+    let terminate = Arc::new(AtomicBool::new(false));
+    #[cfg(unix)]
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&terminate))?;
+
+    // Restores the terminal when it goes out of scope, whichever way main returns
+    let mut guard = TerminalGuard::new()?;
+    if !args.no_title {
+        guard.set_title(format!("{} - hex-editor", input.short_name()))?;
     }
-    Ok(false)
-}
 
-fn draw_screen(content: &[u8], start: usize) -> std::io::Result<()> {
-    let win_size = window_size()?;
-    stdout().execute(SavePosition)?;
-    for i in 0..(win_size.rows as usize - 1) {
-        stdout().execute(MoveTo(0, i as u16))?;
-        draw_line(content, i + start, 16, win_size.columns as usize)?;
+    let mut backend = CrosstermBackend::new().with_size(args.size);
+    // A script that ends in q quits once it has run
+    let mut events = Replay::new(keys, CrosstermEvents);
+    let mut editor = Editor::new(content)
+        .with_fields(args.fields)
+        .with_printable(args.printable)
+        .with_wheel(args.wheel)
+        .with_baseline(baseline)
+        .with_ruler(args.ruler.unwrap_or(RULER_INTERVAL))
+        .with_right_offset(args.offset_both)
+        .with_ascii_left(args.ascii_left)
+        .with_record_size(args.record_size.unwrap_or(0))
+        .with_byte_readout(true)
+        .with_status_format(status_format)
+        .with_file_name(input.name())
+        .with_history(saved_history.clone());
+    for placeholder in unknown {
+        editor.post(
+            Severity::Warning,
+            format!(
+                "unknown {placeholder} in --status, there's {}",
+                status::PLACEHOLDERS
+            ),
+        );
+    }
+    if let Some(message) = history_error {
+        log::warn!("{message}");
+        editor.post(Severity::Warning, message);
     }
-    let message = "Press 'q' to quit";
-    let message = if message.len() < win_size.columns as usize {
-        // if message is shorter than the screen width, pad it with spaces
-        message.to_string()
-            + " "
-                .repeat(win_size.columns as usize - message.len())
-                .as_str()
-    } else {
-        // if message is longer than the screen width, truncate it
-        message[..win_size.columns as usize].to_string()
-    };
-    stdout()
-        .execute(MoveTo(0, win_size.rows - 1))?
-        .execute(Print(message))?
-        .execute(RestorePosition)?;
-    Ok(())
-}
 
-fn main() -> std::io::Result<()> {
-    // Get first command line argument
-    let path = std::env::args().nth(1).unwrap();
-
-    let content = std::fs::read(path)?;
-
-    let mut start = 0usize;
-
-    enable_raw_mode()?;
-
-    let total_lines = (content.len() as f64 / LINE_LENGTH as f64).ceil() as usize;
-
-    draw_screen(&content, start)?;
-
-    stdout()
-        .execute(MoveTo(10, 0))?
-        .execute(EnableMouseCapture)?;
-
-    loop {
-        let requires_redraw = match read()? {
-            Event::Key(event) => match event.code {
-                Char('q') => break,
-                Char('h') => move_cursor(&mut start, total_lines, Direction::Left)?,
-                Char('j') => move_cursor(&mut start, total_lines, Direction::Down)?,
-                Char('k') => move_cursor(&mut start, total_lines, Direction::Up)?,
-                Char('l') => move_cursor(&mut start, total_lines, Direction::Right)?,
-                Char('0') => {
-                    let pos = cursor::position()?;
-                    stdout().execute(MoveTo(10, pos.1))?;
-                    true
-                }
-                Char('$') => {
-                    let pos = cursor::position()?;
-                    stdout().execute(MoveTo(10 + LINE_LENGTH as u16 * 3, pos.1))?;
-                    false
-                }
-                Char('g') => goto_start(&mut start)?,
-                Char('G') => goto_end(&mut start, total_lines)?,
-                _ => false,
-            },
-
-            Event::Mouse(e) => match e.kind {
-                MouseEventKind::ScrollUp => move_cursor(&mut start, total_lines, Direction::Up)?,
-                MouseEventKind::ScrollDown => {
-                    move_cursor(&mut start, total_lines, Direction::Down)?
-                }
-                _ => false,
-            },
-            Event::Resize(_, _) => true,
-            _ => false,
-        };
-
-        if requires_redraw {
-            draw_screen(&content, start)?;
+    editor.draw(&mut backend)?;
+
+    while !terminate.load(Ordering::Relaxed) {
+        match editor.tick(&mut backend, &mut events)? {
+            Outcome::Continue => {}
+            Outcome::Quit => break,
+            Outcome::Suspend => {
+                log::info!("suspending");
+                #[cfg(unix)]
+                guard.suspend()?;
+                // Everything was lost while we were away, and the window may have changed size
+                editor.draw(&mut backend)?;
+            }
+        }
+    }
+
+    log::info!("exiting");
+    // Back in the shell, where a warning can still be read
+    drop(guard);
+    if let Some(dir) = &data_dir {
+        if *editor.history() != saved_history {
+            if let Err(err) = editor.history().save(dir) {
+                eprintln!(
+                    "hex-editor: cannot save the history in {}: {err}",
+                    dir.display()
+                );
+            }
         }
     }
 
@@ -0,0 +1,201 @@
+//! Short-lived messages shown in the status line in place of the usual hint.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crossterm::style::Color;
+
+use crate::backend::Style;
+
+/// Most messages waiting to be shown, older ones are dropped past this
+const MAX_QUEUED: usize = 4;
+
+/// Most messages `:messages` goes back through
+const MAX_LOGGED: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    /// Something didn't go quite as asked, like a jump past the end
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+
+    pub fn style(self) -> Style {
+        match self {
+            Severity::Info => Style::default(),
+            Severity::Warning => Style {
+                fg: Some(Color::Yellow),
+                bg: None,
+            },
+            Severity::Error => Style {
+                fg: Some(Color::White),
+                bg: Some(Color::DarkRed),
+            },
+        }
+    }
+}
+
+struct Message {
+    text: String,
+    severity: Severity,
+    /// When it reached the front of the queue, `None` until then
+    shown_at: Option<Instant>,
+}
+
+/// Messages in the order they were posted, the first one being on screen
+#[derive(Default)]
+pub struct Messages {
+    queue: VecDeque<Message>,
+    /// Every recent message, oldest first, whether it was shown or not
+    log: VecDeque<(Severity, String)>,
+}
+
+impl Messages {
+    /// Queues `text` behind the messages already up. Posting the same text
+    /// again only keeps it up for longer.
+    pub fn post(&mut self, severity: Severity, text: String, now: Instant) {
+        if self.log.back() != Some(&(severity, text.clone())) {
+            if self.log.len() == MAX_LOGGED {
+                self.log.pop_front();
+            }
+            self.log.push_back((severity, text.clone()));
+        }
+        if let Some(message) = self.queue.iter_mut().find(|m| m.text == text) {
+            message.severity = severity;
+            if message.shown_at.is_some() {
+                message.shown_at = Some(now);
+            }
+            return;
+        }
+        if self.queue.len() == MAX_QUEUED {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(Message {
+            text,
+            severity,
+            shown_at: None,
+        });
+        self.show_front(now);
+    }
+
+    /// The messages posted recently, oldest first and without repeats in a
+    /// row
+    pub fn log(&self) -> impl Iterator<Item = (Severity, &str)> {
+        self.log
+            .iter()
+            .map(|(severity, text)| (*severity, text.as_str()))
+    }
+
+    /// The message on screen
+    pub fn current(&self) -> Option<(&str, Severity)> {
+        self.queue.front().map(|m| (m.text.as_str(), m.severity))
+    }
+
+    /// Drops every message, returning whether any was up
+    pub fn clear(&mut self) -> bool {
+        let any = !self.queue.is_empty();
+        self.queue.clear();
+        any
+    }
+
+    /// Moves on to the next message once the current one has been up for
+    /// `timeout`. Returns whether the message on screen changed.
+    pub fn expire(&mut self, now: Instant, timeout: Duration) -> bool {
+        let expired = self.queue.front().is_some_and(|m| {
+            m.shown_at
+                .is_some_and(|shown_at| now.duration_since(shown_at) >= timeout)
+        });
+        if expired {
+            self.queue.pop_front();
+            self.show_front(now);
+        }
+        expired
+    }
+
+    fn show_front(&mut self, now: Instant) {
+        if let Some(front) = self.queue.front_mut() {
+            front.shown_at.get_or_insert(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    #[test]
+    fn queued_messages_take_turns() {
+        let now = Instant::now();
+        let mut messages = Messages::default();
+        messages.post(Severity::Info, "one".into(), now);
+        messages.post(Severity::Error, "two".into(), now + TIMEOUT / 2);
+        assert_eq!(messages.current(), Some(("one", Severity::Info)));
+
+        assert!(messages.expire(now + TIMEOUT, TIMEOUT));
+        // The second one gets its full time on screen
+        assert!(!messages.expire(now + TIMEOUT * 3 / 2, TIMEOUT));
+        assert_eq!(messages.current(), Some(("two", Severity::Error)));
+        assert!(messages.expire(now + TIMEOUT * 2, TIMEOUT));
+        assert_eq!(messages.current(), None);
+        assert!(!messages.expire(now + TIMEOUT * 3, TIMEOUT));
+    }
+
+    #[test]
+    fn repeats_collapse() {
+        let now = Instant::now();
+        let mut messages = Messages::default();
+        messages.post(Severity::Info, "saved".into(), now);
+        messages.post(Severity::Info, "saved".into(), now + TIMEOUT / 2);
+        assert!(!messages.expire(now + TIMEOUT, TIMEOUT));
+        assert!(messages.expire(now + TIMEOUT * 3 / 2, TIMEOUT));
+        assert_eq!(messages.current(), None);
+    }
+
+    #[test]
+    fn oldest_are_dropped() {
+        let now = Instant::now();
+        let mut messages = Messages::default();
+        for i in 0..=MAX_QUEUED {
+            messages.post(Severity::Warning, i.to_string(), now);
+        }
+        assert_eq!(messages.current(), Some(("1", Severity::Warning)));
+        assert!(messages.clear());
+        assert!(!messages.clear());
+    }
+
+    #[test]
+    fn log_keeps_what_was_dropped() {
+        let now = Instant::now();
+        let mut messages = Messages::default();
+        messages.post(Severity::Info, "one".into(), now);
+        messages.post(Severity::Info, "one".into(), now);
+        messages.post(Severity::Error, "two".into(), now);
+        messages.clear();
+        messages.post(Severity::Info, "one".into(), now);
+        let log: Vec<_> = messages.log().collect();
+        assert_eq!(
+            log,
+            [
+                (Severity::Info, "one"),
+                (Severity::Error, "two"),
+                (Severity::Info, "one")
+            ]
+        );
+        for i in 0..MAX_LOGGED {
+            messages.post(Severity::Info, i.to_string(), now);
+        }
+        assert_eq!(messages.log().next(), Some((Severity::Info, "0")));
+    }
+}
@@ -0,0 +1,122 @@
+//! Which bytes the ASCII pane draws as characters, everything else is a dot.
+
+/// Glyphs Windows-1252 gives the C1 control range 0x80..=0x9f, `None` where
+/// it leaves them undefined
+const CP1252_HIGH: [Option<char>; 32] = [
+    Some('€'),
+    None,
+    Some('‚'),
+    Some('ƒ'),
+    Some('„'),
+    Some('…'),
+    Some('†'),
+    Some('‡'),
+    Some('ˆ'),
+    Some('‰'),
+    Some('Š'),
+    Some('‹'),
+    Some('Œ'),
+    None,
+    Some('Ž'),
+    None,
+    None,
+    Some('‘'),
+    Some('’'),
+    Some('“'),
+    Some('”'),
+    Some('•'),
+    Some('–'),
+    Some('—'),
+    Some('˜'),
+    Some('™'),
+    Some('š'),
+    Some('›'),
+    Some('œ'),
+    None,
+    Some('ž'),
+    Some('Ÿ'),
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Printable {
+    /// 0x20..=0x7e
+    #[default]
+    Ascii,
+    /// ASCII plus 0xa0..=0xff as their ISO-8859-1 glyphs
+    Latin1,
+    /// Anything but the C0 controls and DEL. High bytes are drawn as
+    /// Latin-1, and the C1 range as Windows-1252 where it has glyphs.
+    All,
+}
+
+impl Printable {
+    pub const NAMES: &'static str = "ascii|latin1|all";
+
+    pub fn parse(name: &str) -> Option<Printable> {
+        match name {
+            "ascii" => Some(Printable::Ascii),
+            "latin1" => Some(Printable::Latin1),
+            "all" => Some(Printable::All),
+            _ => None,
+        }
+    }
+
+    /// What the ASCII pane shows for `byte`
+    pub fn glyph(self, byte: u8) -> char {
+        let glyph = match (self, byte) {
+            (_, 0x20..=0x7e) => Some(byte as char),
+            (Printable::Latin1 | Printable::All, 0xa0..=0xff) => Some(byte as char),
+            (Printable::All, 0x80..=0x9f) => CP1252_HIGH[byte as usize - 0x80],
+            _ => None,
+        };
+        glyph.unwrap_or('.')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_is_strict() {
+        assert_eq!(Printable::Ascii.glyph(b' '), ' ');
+        assert_eq!(Printable::Ascii.glyph(b'~'), '~');
+        for byte in [0x00, 0x1f, 0x7f, 0x80, 0xa0, 0xe9, 0xff] {
+            assert_eq!(Printable::Ascii.glyph(byte), '.', "{byte:#x}");
+        }
+    }
+
+    #[test]
+    fn latin1_adds_high_half() {
+        assert_eq!(Printable::Latin1.glyph(0xe9), 'é');
+        assert_eq!(Printable::Latin1.glyph(0xa0), '\u{a0}');
+        assert_eq!(Printable::Latin1.glyph(0x80), '.');
+        assert_eq!(Printable::Latin1.glyph(0x09), '.');
+    }
+
+    #[test]
+    fn all_fills_c1_range() {
+        assert_eq!(Printable::All.glyph(0x80), '€');
+        assert_eq!(Printable::All.glyph(0x81), '.');
+        assert_eq!(Printable::All.glyph(0xff), 'ÿ');
+        assert_eq!(Printable::All.glyph(0x7f), '.');
+        assert_eq!(Printable::All.glyph(0x0a), '.');
+    }
+
+    #[test]
+    fn every_glyph_is_one_cell_of_visible_text() {
+        for mode in [Printable::Ascii, Printable::Latin1, Printable::All] {
+            for byte in 0..=255 {
+                assert!(!mode.glyph(byte).is_control(), "{mode:?} {byte:#x}");
+            }
+        }
+    }
+
+    #[test]
+    fn parse_names() {
+        for name in Printable::NAMES.split('|') {
+            assert!(Printable::parse(name).is_some(), "{name}");
+        }
+        assert_eq!(Printable::parse("utf8"), None);
+    }
+}
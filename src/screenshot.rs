@@ -0,0 +1,272 @@
+//! Saving what's on screen for `:screenshot`, as SVG or as text with ANSI
+//! colors.
+
+use std::fmt::Write;
+
+use crossterm::style::Color;
+
+use crate::backend::{Cell, MemoryBackend, Style};
+
+/// Size of a cell in the SVG, in pixels. Monospace fonts are about 0.6 of
+/// their size wide, `textLength` squeezes them into the rest.
+const CELL_WIDTH: usize = 9;
+const CELL_HEIGHT: usize = 17;
+const FONT_SIZE: usize = 14;
+/// Colors for cells that leave them to the terminal
+const DEFAULT_FG: (u8, u8, u8) = (0xd0, 0xd0, 0xd0);
+const DEFAULT_BG: (u8, u8, u8) = (0x1e, 0x1e, 0x1e);
+
+/// The first 16 colors the way xterm draws them
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+/// The screen as an SVG image, a rectangle for every background and a text
+/// element for every run of cells that look the same
+pub fn svg(screen: &MemoryBackend) -> String {
+    let rows: Vec<&[Cell]> = screen.rows().collect();
+    let columns = rows.first().map_or(0, |row| row.len());
+    let mut svg = String::new();
+    // Writing to a String can't fail
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"monospace\" font-size=\"{FONT_SIZE}\">",
+        columns * CELL_WIDTH,
+        rows.len() * CELL_HEIGHT,
+    );
+    let _ = writeln!(
+        svg,
+        "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        hex(DEFAULT_BG)
+    );
+    for (row, cells) in rows.iter().enumerate() {
+        let y = row * CELL_HEIGHT;
+        for (col, run) in runs(cells) {
+            let x = col * CELL_WIDTH;
+            let style = run[0].style;
+            if let Some(bg) = style.bg.and_then(rgb) {
+                let _ = writeln!(
+                    svg,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{}\" height=\"{CELL_HEIGHT}\" \
+                     fill=\"{}\"/>",
+                    run.len() * CELL_WIDTH,
+                    hex(bg)
+                );
+            }
+            let text: String = run.iter().map(|cell| cell.symbol).collect();
+            let text = text.trim_end();
+            if text.is_empty() {
+                continue;
+            }
+            let width = text.chars().count() * CELL_WIDTH;
+            let fg = style.fg.and_then(rgb).unwrap_or(DEFAULT_FG);
+            // textLength keeps the columns lined up whichever font is used,
+            // even where the cell width isn't quite right for it
+            let _ = writeln!(
+                svg,
+                "<text x=\"{x}\" y=\"{}\" fill=\"{}\" textLength=\"{width}\" \
+                 xml:space=\"preserve\">{}</text>",
+                y + FONT_SIZE,
+                hex(fg),
+                escape(text)
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The screen as lines of text with ANSI escapes for the colors, for `cat`
+/// or `less -R`
+pub fn ansi(screen: &MemoryBackend) -> String {
+    let mut text = String::new();
+    for cells in screen.rows() {
+        let used = cells
+            .iter()
+            .rposition(|cell| *cell != Cell::default())
+            .map_or(0, |last| last + 1);
+        for (_, run) in runs(&cells[..used]) {
+            let symbols: String = run.iter().map(|cell| cell.symbol).collect();
+            let style = run[0].style;
+            if style == Style::default() {
+                text.push_str(&symbols);
+                continue;
+            }
+            let codes: Vec<String> = [style.fg.and_then(|fg| sgr(fg, false))]
+                .into_iter()
+                .chain([style.bg.and_then(|bg| sgr(bg, true))])
+                .flatten()
+                .collect();
+            let _ = write!(text, "\x1b[{}m{symbols}\x1b[0m", codes.join(";"));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Consecutive cells with the same style, with the column of the first one
+fn runs(cells: &[Cell]) -> impl Iterator<Item = (usize, &[Cell])> {
+    let mut col = 0;
+    cells.chunk_by(|a, b| a.style == b.style).map(move |run| {
+        col += run.len();
+        (col - run.len(), run)
+    })
+}
+
+/// Where `color` is in the 16 color palette, for the colors that have a name
+fn palette_index(color: Color) -> Option<u8> {
+    Some(match color {
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15,
+        Color::Reset | Color::Rgb { .. } | Color::AnsiValue(_) => return None,
+    })
+}
+
+/// What `color` looks like, `None` for the terminal's default
+fn rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb { r, g, b } => Some((r, g, b)),
+        Color::AnsiValue(value @ 0..=15) => Some(PALETTE[value as usize]),
+        // The 6x6x6 color cube
+        Color::AnsiValue(value @ 16..=231) => {
+            let level = |n: u8| if n == 0 { 0 } else { 55 + n * 40 };
+            let value = value - 16;
+            Some((level(value / 36), level(value / 6 % 6), level(value % 6)))
+        }
+        // The grey ramp
+        Color::AnsiValue(value) => {
+            let grey = 8 + (value - 232) * 10;
+            Some((grey, grey, grey))
+        }
+        named => palette_index(named).map(|index| PALETTE[index as usize]),
+    }
+}
+
+/// The SGR parameters that set `color` as the foreground or background
+fn sgr(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let layer = if background { 48 } else { 38 };
+    Some(match color {
+        Color::Reset => return None,
+        Color::Rgb { r, g, b } => format!("{layer};2;{r};{g};{b}"),
+        Color::AnsiValue(value) => format!("{layer};5;{value}"),
+        named => {
+            let index = palette_index(named)?;
+            match index {
+                0..=7 => (30 + offset + index).to_string(),
+                _ => (90 + offset + index - 8).to_string(),
+            }
+        }
+    })
+}
+
+fn hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::Backend;
+
+    fn screen() -> MemoryBackend {
+        let mut screen = MemoryBackend::new(8, 2);
+        screen.print("a<b", Style::default()).unwrap();
+        let style = Style {
+            fg: Some(Color::Black),
+            bg: Some(Color::Yellow),
+        };
+        screen.print("cd", style).unwrap();
+        screen.move_to(0, 1).unwrap();
+        screen
+            .print(
+                "e",
+                Style {
+                    fg: Some(Color::AnsiValue(196)),
+                    bg: None,
+                },
+            )
+            .unwrap();
+        screen
+    }
+
+    #[test]
+    fn colors_match_the_terminal() {
+        assert_eq!(rgb(Color::Reset), None);
+        assert_eq!(rgb(Color::DarkCyan), Some((0x00, 0xcd, 0xcd)));
+        assert_eq!(rgb(Color::AnsiValue(6)), rgb(Color::DarkCyan));
+        assert_eq!(rgb(Color::AnsiValue(16)), Some((0, 0, 0)));
+        assert_eq!(rgb(Color::AnsiValue(196)), Some((0xff, 0, 0)));
+        assert_eq!(rgb(Color::AnsiValue(231)), Some((0xff, 0xff, 0xff)));
+        assert_eq!(rgb(Color::AnsiValue(232)), Some((8, 8, 8)));
+        assert_eq!(rgb(Color::AnsiValue(255)), Some((238, 238, 238)));
+        assert_eq!(sgr(Color::DarkRed, false).as_deref(), Some("31"));
+        assert_eq!(sgr(Color::Yellow, true).as_deref(), Some("103"));
+        assert_eq!(sgr(Color::AnsiValue(17), true).as_deref(), Some("48;5;17"));
+    }
+
+    #[test]
+    fn ansi_colors_runs() {
+        assert_eq!(
+            ansi(&screen()),
+            "a<b\x1b[30;103mcd\x1b[0m\n\x1b[38;5;196me\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn svg_has_backgrounds_and_text() {
+        let svg = svg(&screen());
+        assert!(svg.starts_with("<svg "), "{svg}");
+        assert!(svg.contains(">a&lt;b</text>"), "{svg}");
+        assert!(svg.contains("<rect x=\"27\" y=\"0\" width=\"18\""), "{svg}");
+        assert!(svg.contains("fill=\"#000000\" textLength=\"18\""), "{svg}");
+        assert!(svg.contains("fill=\"#ff0000\""), "{svg}");
+        // Blank runs are left to the background
+        assert_eq!(svg.matches("<text").count(), 3);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,103 @@
+//! What `/` looks for and finding it. Patterns are hex in the hex pane and
+//! text in the ASCII pane.
+
+use crate::editor::Pane;
+use crate::error::{Error, Result};
+
+/// The bytes a pattern typed in `pane` stands for. Whitespace between hex
+/// digits is ignored, and a nibble without its pair is left out so a
+/// pattern still being typed matches on the bytes completed so far.
+pub fn parse_pattern(text: &str, pane: Pane) -> Result<Vec<u8>> {
+    if pane == Pane::Ascii {
+        return Ok(text.as_bytes().to_vec());
+    }
+    let digits = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| Error::Parse("not hex".to_string()))?;
+    Ok(digits
+        .chunks_exact(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect())
+}
+
+/// Offset of the first match of `needle` in `haystack` after `from`,
+/// starting over from the beginning if there's none before the end
+pub fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    let (&first, rest) = needle.split_first()?;
+    let matches_at = |offset: usize| haystack[offset + 1..].starts_with(rest);
+    let last = haystack.len().checked_sub(needle.len())?;
+    let start = from.saturating_add(1).min(last + 1);
+    // Looking for the first byte alone is much faster than comparing the
+    // whole needle everywhere, which keeps typing responsive on big files
+    let after = haystack[start..=last]
+        .iter()
+        .enumerate()
+        .filter(|&(_, &byte)| byte == first)
+        .map(|(i, _)| start + i)
+        .find(|&offset| matches_at(offset));
+    after.or_else(|| {
+        haystack[..start]
+            .iter()
+            .enumerate()
+            .filter(|&(i, &byte)| byte == first && i <= last)
+            .map(|(i, _)| i)
+            .find(|&offset| matches_at(offset))
+    })
+}
+
+/// Like `find`, but for the last match before `from`, starting over from
+/// the end
+pub fn find_back(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return None;
+    }
+    let last = haystack.len() - needle.len();
+    let from = from.min(last + 1);
+    let matches_at = |&offset: &usize| haystack[offset..].starts_with(needle);
+    (0..from)
+        .rev()
+        .find(matches_at)
+        .or_else(|| (from..=last).rev().find(matches_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_and_text() {
+        assert_eq!(parse_pattern("de aD B", Pane::Hex).unwrap(), [0xde, 0xad]);
+        assert_eq!(parse_pattern("7", Pane::Hex).unwrap(), []);
+        assert_eq!(parse_pattern("dead", Pane::Ascii).unwrap(), b"dead");
+        assert!(parse_pattern("dx", Pane::Hex).is_err());
+    }
+
+    #[test]
+    fn finds_after_the_cursor_and_wraps() {
+        let haystack = b"abcabcab";
+        assert_eq!(find(haystack, b"ab", 0), Some(3));
+        assert_eq!(find(haystack, b"ab", 3), Some(6));
+        assert_eq!(find(haystack, b"ab", 6), Some(0));
+        assert_eq!(find(haystack, b"abc", 3), Some(0));
+        assert_eq!(find(haystack, b"c", 7), Some(2));
+        assert_eq!(find(haystack, b"x", 0), None);
+        assert_eq!(find(haystack, b"", 0), None);
+        assert_eq!(find(b"ab", b"abc", 0), None);
+        // The byte under the cursor only matches once everything else has
+        assert_eq!(find(b"a", b"a", 0), Some(0));
+    }
+
+    #[test]
+    fn finds_backwards() {
+        let haystack = b"abcabcab";
+        assert_eq!(find_back(haystack, b"ab", 6), Some(3));
+        assert_eq!(find_back(haystack, b"ab", 3), Some(0));
+        assert_eq!(find_back(haystack, b"ab", 0), Some(6));
+        assert_eq!(find_back(haystack, b"abc", 0), Some(3));
+        assert_eq!(find_back(b"a", b"a", 0), Some(0));
+        assert_eq!(find_back(haystack, b"x", 4), None);
+    }
+}
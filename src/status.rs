@@ -0,0 +1,253 @@
+//! The status line as a format string given with `--status`, like
+//! `{file} {offset} / {size} ({percent}%)`.
+
+use std::fmt::Write;
+
+/// Every placeholder, for the warning about ones that don't exist
+pub const PLACEHOLDERS: &str = "{file} {offset} {offset:dec} {byte} {byte:dec} {size} \
+                                {size:hex} {percent} {line}";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    /// The file name, shortened from the start when the line doesn't fit
+    File,
+    /// Offset of the cursor, in hex unless `:dec`
+    Offset { hex: bool },
+    /// Value of the byte under the cursor, in hex unless `:dec`
+    Byte { hex: bool },
+    /// Size of the data in bytes, in decimal unless `:hex`
+    Size { hex: bool },
+    /// How far into the data the cursor is
+    Percent,
+    /// Line the cursor is on, counting from 0 like `G` does
+    Line,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Field> {
+        Some(match name {
+            "file" => Field::File,
+            "offset" | "offset:hex" => Field::Offset { hex: true },
+            "offset:dec" => Field::Offset { hex: false },
+            "byte" | "byte:hex" => Field::Byte { hex: true },
+            "byte:dec" => Field::Byte { hex: false },
+            "size" | "size:dec" => Field::Size { hex: false },
+            "size:hex" => Field::Size { hex: true },
+            "percent" => Field::Percent,
+            "line" => Field::Line,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Piece {
+    Text(String),
+    Field(Field),
+}
+
+/// What the placeholders stand for when the line is drawn
+pub struct Values<'a> {
+    pub file: &'a str,
+    pub offset: usize,
+    /// `None` for empty data
+    pub byte: Option<u8>,
+    pub size: usize,
+    pub line: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusFormat {
+    pieces: Vec<Piece>,
+}
+
+impl StatusFormat {
+    /// Parses a format with `{name}` placeholders, `{{` and `}}` standing for
+    /// braces. Returns the placeholders that don't exist too, they're kept
+    /// as they were written.
+    pub fn parse(text: &str) -> (StatusFormat, Vec<String>) {
+        let mut pieces = Vec::new();
+        let mut unknown = Vec::new();
+        let mut literal = String::new();
+        let mut rest = text;
+        while let Some(i) = rest.find(['{', '}']) {
+            literal.push_str(&rest[..i]);
+            let tail = &rest[i..];
+            if tail.starts_with("{{") || tail.starts_with("}}") {
+                literal.push_str(&tail[..1]);
+                rest = &tail[2..];
+                continue;
+            }
+            let field = tail.strip_prefix('{').and_then(|tail| tail.split_once('}'));
+            match field {
+                Some((name, after)) => {
+                    match Field::parse(name) {
+                        Some(field) => {
+                            if !literal.is_empty() {
+                                pieces.push(Piece::Text(std::mem::take(&mut literal)));
+                            }
+                            pieces.push(Piece::Field(field));
+                        }
+                        None => {
+                            unknown.push(format!("{{{name}}}"));
+                            let _ = write!(literal, "{{{name}}}");
+                        }
+                    }
+                    rest = after;
+                }
+                // A lone brace is just a brace
+                None => {
+                    literal.push_str(&tail[..1]);
+                    rest = &tail[1..];
+                }
+            }
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            pieces.push(Piece::Text(literal));
+        }
+        (StatusFormat { pieces }, unknown)
+    }
+
+    /// The line for `values`, fitted into `width` columns by shortening the
+    /// file name if there is one. Other fields are never shortened, so on
+    /// very narrow screens the result can still be too wide.
+    pub fn render(&self, values: &Values, width: usize) -> String {
+        let mut line = String::new();
+        let mut files = 0;
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => line.push_str(text),
+                Piece::Field(Field::File) => files += 1,
+                Piece::Field(field) => write_field(&mut line, *field, values),
+            }
+        }
+        let fixed = line.chars().count();
+        let file_len = values.file.chars().count();
+        let file = if files == 0 || fixed + files * file_len <= width {
+            values.file.to_string()
+        } else {
+            elide(values.file, width.saturating_sub(fixed) / files)
+        };
+
+        line.clear();
+        for piece in &self.pieces {
+            match piece {
+                Piece::Text(text) => line.push_str(text),
+                Piece::Field(Field::File) => line.push_str(&file),
+                Piece::Field(field) => write_field(&mut line, *field, values),
+            }
+        }
+        line
+    }
+}
+
+fn write_field(line: &mut String, field: Field, values: &Values) {
+    // Writing to a String can't fail
+    let _ = match field {
+        Field::File => Ok(()),
+        Field::Offset { hex: true } => write!(line, "{:#x}", values.offset),
+        Field::Offset { hex: false } => write!(line, "{}", values.offset),
+        Field::Byte { hex } => match values.byte {
+            Some(byte) if hex => write!(line, "{byte:#04x}"),
+            Some(byte) => write!(line, "{byte}"),
+            None => write!(line, "-"),
+        },
+        Field::Size { hex: true } => write!(line, "{:#x}", values.size),
+        Field::Size { hex: false } => write!(line, "{}", values.size),
+        Field::Percent => {
+            let percent = match values.size {
+                0 => 100,
+                size => (values.offset + 1) * 100 / size,
+            };
+            write!(line, "{percent}")
+        }
+        Field::Line => write!(line, "{}", values.line),
+    };
+}
+
+/// The byte under the cursor in hex, decimal, octal, binary and as a
+/// character, `^X` for control characters and `\xNN` past ASCII. `--` for
+/// empty data.
+pub fn byte_readout(byte: Option<u8>) -> String {
+    let Some(byte) = byte else {
+        return "--".to_string();
+    };
+    let char = match byte {
+        0x00..=0x1f => format!("^{}", (byte + 0x40) as char),
+        0x7f => "^?".to_string(),
+        0x80.. => format!("\\x{byte:02x}"),
+        _ => format!("'{}'", byte as char),
+    };
+    format!("{byte:#04x} {byte} {byte:#o} {byte:#010b} {char}")
+}
+
+/// The end of `text` in at most `width` columns, with `...` in front when
+/// some of it had to go. Paths keep their file name that way.
+fn elide(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    if len <= width {
+        return text.to_string();
+    }
+    if width <= 3 {
+        return ".".repeat(width);
+    }
+    let kept: String = text.chars().skip(len - (width - 3)).collect();
+    format!("...{kept}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(file: &str) -> Values<'_> {
+        Values {
+            file,
+            offset: 0x1f,
+            byte: Some(0x41),
+            size: 64,
+            line: 1,
+        }
+    }
+
+    #[test]
+    fn fills_in_placeholders() {
+        let (format, unknown) =
+            StatusFormat::parse("{file} {offset}/{size:hex} {byte:dec} {percent}% {{line {line}}}");
+        assert!(unknown.is_empty());
+        assert_eq!(
+            format.render(&values("a.bin"), 80),
+            "a.bin 0x1f/0x40 65 50% {line 1}"
+        );
+        let (format, _) = StatusFormat::parse("{offset:dec} {byte} {size}");
+        assert_eq!(format.render(&values(""), 80), "31 0x41 64");
+    }
+
+    #[test]
+    fn unknown_placeholders_stay_as_written() {
+        let (format, unknown) = StatusFormat::parse("{mode} at {offset} {");
+        assert_eq!(unknown, ["{mode}"]);
+        assert_eq!(format.render(&values(""), 80), "{mode} at 0x1f {");
+    }
+
+    #[test]
+    fn byte_in_every_base() {
+        assert_eq!(byte_readout(Some(b'A')), "0x41 65 0o101 0b01000001 'A'");
+        assert_eq!(byte_readout(Some(0)), "0x00 0 0o0 0b00000000 ^@");
+        assert_eq!(byte_readout(Some(0x1b)), "0x1b 27 0o33 0b00011011 ^[");
+        assert_eq!(byte_readout(Some(0x7f)), "0x7f 127 0o177 0b01111111 ^?");
+        assert_eq!(byte_readout(Some(0xff)), "0xff 255 0o377 0b11111111 \\xff");
+        assert_eq!(byte_readout(None), "--");
+    }
+
+    #[test]
+    fn shortens_the_file_name_to_fit() {
+        let (format, _) = StatusFormat::parse("{file} {offset}");
+        let file = "/very/long/path/to/some.bin";
+        assert_eq!(format.render(&values(file), 80), format!("{file} 0x1f"));
+        assert_eq!(format.render(&values(file), 16), "...some.bin 0x1f");
+        assert_eq!(format.render(&values(file), 6), ". 0x1f");
+        assert_eq!(elide("abcdef", 4), "...f");
+        assert_eq!(elide("abcdef", 2), "..");
+    }
+}
@@ -0,0 +1,159 @@
+//! Guessing how a run of text at some offset is encoded, by decoding it every
+//! way we know and seeing how far each one gets.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Ascii,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    pub const ALL: [Encoding; 4] = [
+        Encoding::Ascii,
+        Encoding::Utf8,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Ascii => "ascii",
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+        }
+    }
+}
+
+/// The text one encoding makes of the bytes at an offset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub encoding: Encoding,
+    pub text: String,
+    /// Number of bytes the text was decoded from
+    pub len: usize,
+}
+
+/// Decodes the run of printable text starting at `offset` in every encoding,
+/// reading at most `max_len` bytes. Each run stops at the first control
+/// character or invalid sequence, and encodings that don't get a single
+/// character out of the bytes are left out.
+pub fn decode_string_candidates(content: &[u8], offset: usize, max_len: usize) -> Vec<Candidate> {
+    let bytes = content.get(offset..).unwrap_or_default();
+    let bytes = &bytes[..bytes.len().min(max_len)];
+    Encoding::ALL
+        .into_iter()
+        .map(|encoding| {
+            let (text, len) = match encoding {
+                Encoding::Ascii => decode_ascii(bytes),
+                Encoding::Utf8 => decode_utf8(bytes),
+                Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+                Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+            };
+            Candidate {
+                encoding,
+                text,
+                len,
+            }
+        })
+        .filter(|candidate| candidate.len > 0)
+        .collect()
+}
+
+/// The longest run of printable characters in `chars`, as the text and the
+/// number of bytes it took
+fn printable_run(chars: impl Iterator<Item = (char, usize)>) -> (String, usize) {
+    let mut text = String::new();
+    let mut len = 0;
+    for (c, width) in chars.take_while(|(c, _)| !c.is_control()) {
+        text.push(c);
+        len += width;
+    }
+    (text, len)
+}
+
+fn decode_ascii(bytes: &[u8]) -> (String, usize) {
+    printable_run(
+        bytes
+            .iter()
+            .take_while(|byte| byte.is_ascii())
+            .map(|&byte| (byte as char, 1)),
+    )
+}
+
+fn decode_utf8(bytes: &[u8]) -> (String, usize) {
+    let valid = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        // Everything before the error is valid by definition
+        Err(err) => std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap_or_default(),
+    };
+    printable_run(valid.chars().map(|c| (c, c.len_utf8())))
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> (String, usize) {
+    let units = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]]));
+    printable_run(
+        char::decode_utf16(units)
+            .map_while(Result::ok)
+            .map(|c| (c, c.len_utf16() * 2)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(content: &[u8]) -> Vec<(Encoding, String, usize)> {
+        decode_string_candidates(content, 0, 64)
+            .into_iter()
+            .map(|c| (c.encoding, c.text, c.len))
+            .collect()
+    }
+
+    #[test]
+    fn plain_ascii() {
+        let candidates = texts(b"Hi!\0rest");
+        assert_eq!(candidates[0], (Encoding::Ascii, "Hi!".to_string(), 3));
+        assert_eq!(candidates[1], (Encoding::Utf8, "Hi!".to_string(), 3));
+        // Every pair of ASCII bytes is some CJK character or other in UTF-16
+        assert_eq!(candidates.len(), 4);
+    }
+
+    #[test]
+    fn utf8_goes_past_ascii() {
+        let candidates = texts("héllo\n".as_bytes());
+        assert_eq!(candidates[0], (Encoding::Ascii, "h".to_string(), 1));
+        assert_eq!(candidates[1], (Encoding::Utf8, "héllo".to_string(), 6));
+    }
+
+    #[test]
+    fn utf16_both_ways() {
+        let le: Vec<u8> = "Hey".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let candidates = texts(&[le.as_slice(), &[0, 0]].concat());
+        let utf16le = candidates
+            .iter()
+            .find(|c| c.0 == Encoding::Utf16Le)
+            .unwrap();
+        assert_eq!(utf16le, &(Encoding::Utf16Le, "Hey".to_string(), 6));
+        // H then a NUL, which ends the ASCII run right away
+        assert_eq!(candidates[0], (Encoding::Ascii, "H".to_string(), 1));
+
+        let be: Vec<u8> = "😀!".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        let candidates = texts(&be);
+        let utf16be = candidates
+            .iter()
+            .find(|c| c.0 == Encoding::Utf16Be)
+            .unwrap();
+        assert_eq!(utf16be, &(Encoding::Utf16Be, "😀!".to_string(), 6));
+    }
+
+    #[test]
+    fn limits() {
+        let candidates = decode_string_candidates(b"abcdef", 2, 3);
+        assert_eq!(candidates[0].text, "cde");
+        assert!(decode_string_candidates(b"abc", 10, 3).is_empty());
+        assert!(decode_string_candidates(b"\0\0\0", 0, 3).is_empty());
+    }
+}
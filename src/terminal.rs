@@ -0,0 +1,112 @@
+use std::io::stdout;
+
+use crossterm::cursor::SetCursorStyle;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
+use crossterm::style::Print;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+};
+use crossterm::ExecutableCommand;
+
+/// Saves the window title on the terminal's title stack (xterm and most
+/// terminals since), and puts it back. Terminals without one ignore both.
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+/// Puts the terminal into the state the editor needs and restores it when
+/// dropped, so every exit path (including `?` and panics) leaves the shell
+/// usable.
+pub struct TerminalGuard {
+    raw_mode: bool,
+    alternate_screen: bool,
+    mouse_capture: bool,
+    /// Window title to show while the editor runs
+    title: Option<String>,
+    title_set: bool,
+}
+
+impl TerminalGuard {
+    pub fn new() -> std::io::Result<Self> {
+        let mut guard = TerminalGuard {
+            raw_mode: false,
+            alternate_screen: false,
+            mouse_capture: false,
+            title: None,
+            title_set: false,
+        };
+        guard.enter()?;
+        Ok(guard)
+    }
+
+    /// Shows `title` as the window title until the terminal is restored,
+    /// and again whenever it's set up after that
+    pub fn set_title(&mut self, title: String) -> std::io::Result<()> {
+        self.title = Some(title);
+        self.show_title()
+    }
+
+    fn show_title(&mut self) -> std::io::Result<()> {
+        let Some(title) = &self.title else {
+            return Ok(());
+        };
+        let mut out = stdout();
+        if !self.title_set {
+            out.execute(Print(PUSH_TITLE))?;
+            self.title_set = true;
+        }
+        out.execute(SetTitle(title))?;
+        Ok(())
+    }
+
+    fn enter(&mut self) -> std::io::Result<()> {
+        enable_raw_mode()?;
+        self.raw_mode = true;
+        stdout().execute(EnterAlternateScreen)?;
+        self.alternate_screen = true;
+        stdout().execute(EnableMouseCapture)?;
+        self.mouse_capture = true;
+        self.show_title()
+    }
+
+    fn leave(&mut self) {
+        // Errors are ignored here, there is nothing sensible left to do with them
+        let mut out = stdout();
+        if self.mouse_capture {
+            let _ = out.execute(DisableMouseCapture);
+            self.mouse_capture = false;
+        }
+        let _ = out.execute(SetCursorStyle::DefaultUserShape);
+        if self.title_set {
+            let _ = out.execute(Print(POP_TITLE));
+            self.title_set = false;
+        }
+        if self.alternate_screen {
+            let _ = out.execute(LeaveAlternateScreen);
+            self.alternate_screen = false;
+        }
+        if self.raw_mode {
+            let _ = disable_raw_mode();
+            self.raw_mode = false;
+        }
+    }
+
+    /// Hands the terminal back to the shell and stops the process, like Ctrl-Z
+    /// does for a cooked-mode program. Returns once the shell resumes us with
+    /// the terminal set up again; the caller is expected to redraw.
+    #[cfg(unix)]
+    pub fn suspend(&mut self) -> std::io::Result<()> {
+        self.leave();
+        // Stop the whole process group, as the terminal driver would have done.
+        // SIGTSTP's default action stops us right here until SIGCONT arrives.
+        unsafe {
+            libc::kill(0, libc::SIGTSTP);
+        }
+        self.enter()
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        self.leave();
+    }
+}
@@ -0,0 +1,70 @@
+//! Turning timestamps into dates, for the time fields of a layout.
+
+/// Seconds from 1601-01-01, where Windows FILETIMEs count from, to the Unix
+/// epoch
+pub const FILETIME_EPOCH: i64 = 11_644_473_600;
+
+/// `seconds` after the Unix epoch as a UTC date like `2024-02-29 13:05:09`,
+/// with `fraction` after the seconds in `digits` digits if there are any.
+/// `None` for years that don't have four digits.
+pub fn format(seconds: i64, fraction: u32, digits: usize) -> Option<String> {
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+    let (year, month, day) = date(days);
+    if !(0..=9999).contains(&year) {
+        return None;
+    }
+    let mut text = format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    );
+    if digits > 0 {
+        text.push_str(&format!(".{fraction:0digits$}"));
+    }
+    text.push_str(" UTC");
+    Some(text)
+}
+
+/// The year, month and day `days` after 1970-01-01, in the proleptic
+/// Gregorian calendar. See Howard Hinnant's `civil_from_days`.
+fn date(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March, so the leap day comes last
+    let month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+    let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates() {
+        assert_eq!(date(0), (1970, 1, 1));
+        assert_eq!(date(-1), (1969, 12, 31));
+        assert_eq!(date(19_782), (2024, 2, 29));
+        assert_eq!(date(-FILETIME_EPOCH / 86_400), (1601, 1, 1));
+    }
+
+    #[test]
+    fn formats() {
+        assert_eq!(format(0, 0, 0).unwrap(), "1970-01-01 00:00:00 UTC");
+        assert_eq!(
+            format(1_709_211_909, 42, 3).unwrap(),
+            "2024-02-29 13:05:09.042 UTC"
+        );
+        assert_eq!(format(-1, 0, 0).unwrap(), "1969-12-31 23:59:59 UTC");
+        assert_eq!(format(i64::MAX, 0, 0), None);
+        assert_eq!(format(-62_167_219_201, 0, 0), None);
+    }
+}
@@ -0,0 +1,86 @@
+//! Pure scrolling and cursor math, kept in one place so the renderer and the
+//! movement code can't disagree about where things are.
+//!
+//! Lines are indexed from the start of the buffer. Rows are the lines as
+//! they're drawn, where a closed fold takes one row for all of its lines, and
+//! `start` is the row shown at the top of the screen. `Folds` maps between
+//! the two.
+
+/// Rows at the bottom of the terminal that aren't used for data
+pub const STATUS_ROWS: usize = 1;
+
+/// Number of rows available for data on a terminal with `terminal_rows` rows
+pub fn data_rows(terminal_rows: usize) -> usize {
+    terminal_rows.saturating_sub(STATUS_ROWS)
+}
+
+/// Number of lines needed to show `len` bytes, counting a partial last line
+pub fn total_lines(len: usize, line_length: usize) -> usize {
+    len.div_ceil(line_length)
+}
+
+/// The largest `start` that still keeps the screen filled, i.e. the one that
+/// puts the last line on the last data row
+pub fn max_start(total_lines: usize, data_rows: usize) -> usize {
+    total_lines.saturating_sub(data_rows)
+}
+
+pub fn clamp_start(start: usize, total_lines: usize, data_rows: usize) -> usize {
+    start.min(max_start(total_lines, data_rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_rows_leaves_room_for_status() {
+        assert_eq!(data_rows(10), 9);
+        assert_eq!(data_rows(1), 0);
+        assert_eq!(data_rows(0), 0);
+    }
+
+    #[test]
+    fn total_lines_counts_partial_line() {
+        assert_eq!(total_lines(0, 16), 0);
+        assert_eq!(total_lines(1, 16), 1);
+        assert_eq!(total_lines(16, 16), 1);
+        assert_eq!(total_lines(17, 16), 2);
+        assert_eq!(total_lines(32, 16), 2);
+    }
+
+    #[test]
+    fn max_start_file_exactly_fills_screen() {
+        assert_eq!(max_start(9, 9), 0);
+    }
+
+    #[test]
+    fn max_start_one_line_more() {
+        assert_eq!(max_start(10, 9), 1);
+    }
+
+    #[test]
+    fn max_start_one_line_less() {
+        assert_eq!(max_start(8, 9), 0);
+    }
+
+    #[test]
+    fn max_start_partial_last_row_counts_as_a_line() {
+        // 9 full lines and 3 bytes on a tenth
+        assert_eq!(max_start(total_lines(9 * 16 + 3, 16), 9), 1);
+    }
+
+    #[test]
+    fn max_start_without_data_rows() {
+        assert_eq!(max_start(5, 0), 5);
+        assert_eq!(max_start(0, 0), 0);
+    }
+
+    #[test]
+    fn clamp_start_bounds() {
+        assert_eq!(clamp_start(0, 20, 9), 0);
+        assert_eq!(clamp_start(11, 20, 9), 11);
+        assert_eq!(clamp_start(12, 20, 9), 11);
+        assert_eq!(clamp_start(100, 5, 9), 0);
+    }
+}
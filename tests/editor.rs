@@ -0,0 +1,2012 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::style::Color;
+use hex_editor::backend::{EventSource, Style};
+use hex_editor::base64;
+use hex_editor::confirm::{Choice, Question};
+use hex_editor::editor::{Editor, Outcome, Pane, Wheel, STATUS_TIMEOUT};
+use hex_editor::fake::{FakeTerminal, ScriptedEvents, TestBackend};
+use hex_editor::fields::parse_layout;
+use hex_editor::history::Histories;
+use hex_editor::keys::{parse_keys, Replay};
+use hex_editor::message::Severity;
+use hex_editor::printable::Printable;
+use hex_editor::status::StatusFormat;
+
+/// A buffer of `lines` full lines whose bytes count up from zero
+fn content(lines: usize) -> Vec<u8> {
+    (0..lines * 16).map(|i| i as u8).collect()
+}
+
+fn setup(lines: usize, cols: u16, rows: u16) -> (Editor, FakeTerminal) {
+    let editor = Editor::new(content(lines));
+    let mut term = FakeTerminal::new(cols, rows);
+    editor.draw(&mut term.backend).unwrap();
+    (editor, term)
+}
+
+fn offset_of_row(term: &FakeTerminal, row: u16) -> String {
+    term.backend.row_text(row)[..8].to_string()
+}
+
+fn scroll(kind: MouseEventKind) -> Event {
+    mouse(kind, 0, 0)
+}
+
+fn mouse(kind: MouseEventKind, column: u16, row: u16) -> Event {
+    Event::Mouse(MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers: KeyModifiers::NONE,
+    })
+}
+
+#[test]
+fn draws_first_screen() {
+    let (_, term) = setup(30, 80, 10);
+    let lines = term.backend.lines();
+    assert_eq!(
+        lines[0],
+        "00000000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f  ................"
+    );
+    assert_eq!(offset_of_row(&term, 8), "00000080");
+    assert_eq!(lines[9], "Press 'q' to quit");
+    assert_eq!(term.backend.cursor(), (10, 0));
+}
+
+#[test]
+fn j_moves_cursor_then_scrolls() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("jjj");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.cursor(), (10, 3));
+    assert_eq!(editor.start(), 0);
+
+    term.events.keys(&"j".repeat(6));
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.cursor(), (10, 8));
+    assert_eq!(editor.start(), 1);
+    assert_eq!(offset_of_row(&term, 0), "00000010");
+}
+
+#[test]
+fn scrolling_stops_at_end_of_file() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys(&"j".repeat(100));
+    term.run(&mut editor).unwrap();
+    let start = editor.start();
+    let screen = term.backend.lines();
+
+    term.events.keys("jjj");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), start);
+    assert_eq!(term.backend.lines(), screen);
+    assert_eq!(term.backend.cursor(), (10, 8));
+    // The last line is reachable, and drawn on the last data row
+    assert_eq!(offset_of_row(&term, 8), "000001d0");
+}
+
+#[test]
+fn k_at_top_does_nothing() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("kkk");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 0);
+    assert_eq!(term.backend.cursor(), (10, 0));
+}
+
+#[test]
+fn mouse_wheel_scrolls_view() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("jjjj");
+    term.events.push(scroll(MouseEventKind::ScrollDown));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 3);
+    assert_eq!(editor.offset(), 4 * 16);
+    assert_eq!(term.backend.cursor(), (10, 1));
+
+    // The cursor stays put even when it scrolls off screen
+    term.events.push(scroll(MouseEventKind::ScrollDown));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 6);
+    assert_eq!(editor.offset(), 4 * 16);
+    assert_eq!(term.backend.cursor(), (79, 9));
+
+    // The view stops at the end of the data
+    for _ in 0..10 {
+        term.events.push(scroll(MouseEventKind::ScrollDown));
+    }
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 21);
+    assert_eq!(offset_of_row(&term, 8), "000001d0");
+
+    // Moving the cursor brings it back into view
+    term.events.keys("j");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 5 * 16);
+    assert_eq!(editor.start(), 5);
+    assert_eq!(term.backend.cursor(), (10, 0));
+
+    for _ in 0..10 {
+        term.events.push(scroll(MouseEventKind::ScrollUp));
+    }
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 0);
+    assert_eq!(editor.offset(), 5 * 16);
+    assert_eq!(term.backend.cursor(), (10, 5));
+}
+
+#[test]
+fn mouse_wheel_can_move_cursor_like_j_and_k() {
+    let mut editor = Editor::new(content(30)).with_wheel(Wheel::MoveCursor);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+    term.events
+        .push(scroll(MouseEventKind::ScrollDown))
+        .push(scroll(MouseEventKind::ScrollDown))
+        .push(scroll(MouseEventKind::ScrollUp));
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.cursor(), (10, 1));
+}
+
+#[test]
+fn big_g_shows_last_line() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("G");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 30 * 16 - 1);
+    assert_eq!(term.backend.cursor(), (55, 8));
+    assert_eq!(offset_of_row(&term, 8), "000001d0");
+    assert_eq!(term.backend.lines()[9], "Press 'q' to quit");
+}
+
+#[test]
+fn g_returns_to_top() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("Gllg");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 0);
+    assert_eq!(term.backend.cursor(), (10, 0));
+    assert_eq!(offset_of_row(&term, 0), "00000000");
+}
+
+#[test]
+fn resize_redraws_at_new_size() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("jj").resize(40, 6);
+    term.run(&mut editor).unwrap();
+    let lines = term.backend.lines();
+    assert_eq!(lines.len(), 6);
+    assert_eq!(lines[0], "00000000: 00 01 02 03 04 05 06 07 08 09");
+    assert_eq!(lines[5], "Press 'q' to quit");
+    assert_eq!(term.backend.cursor(), (10, 2));
+
+    term.events.resize(100, 20);
+    term.run(&mut editor).unwrap();
+    let lines = term.backend.lines();
+    assert_eq!(lines.len(), 20);
+    assert_eq!(offset_of_row(&term, 18), "00000120");
+    assert_eq!(lines[19], "Press 'q' to quit");
+}
+
+#[test]
+fn q_quits() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("jqj");
+    assert_eq!(term.run(&mut editor).unwrap(), Outcome::Quit);
+    assert_eq!(term.backend.cursor(), (10, 1));
+}
+
+#[test]
+fn ascii_pane_moves_one_cell_per_byte() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events
+        .keys("llll")
+        .key(KeyCode::Tab, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.pane(), Pane::Ascii);
+    assert_eq!(term.backend.cursor(), (61, 0));
+
+    term.events.keys("lllh");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 4);
+    assert_eq!(term.backend.cursor(), (63, 0));
+}
+
+#[test]
+fn panes_share_the_cursor_offset() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events
+        .key(KeyCode::Tab, KeyModifiers::NONE)
+        .keys("lllj")
+        .key(KeyCode::Tab, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.pane(), Pane::Hex);
+    assert_eq!(editor.offset(), 0x13);
+    assert_eq!(term.backend.cursor(), (19, 1));
+}
+
+#[test]
+fn l_steps_over_nibbles_and_skips_separators() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    let mut columns = vec![];
+    for _ in 0..5 {
+        term.events.keys("l");
+        term.run(&mut editor).unwrap();
+        columns.push(term.backend.cursor().0);
+    }
+    assert_eq!(columns, [11, 13, 14, 16, 17]);
+
+    term.events.keys("hhh");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.cursor(), (13, 0));
+    assert_eq!(editor.offset(), 1);
+}
+
+#[test]
+fn zero_and_dollar_land_on_byte_cells() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("j$");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x1f);
+    assert_eq!(term.backend.cursor(), (56, 1));
+
+    term.events.keys("0");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x10);
+    assert_eq!(term.backend.cursor(), (10, 1));
+}
+
+#[test]
+fn click_moves_cursor_to_cell() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 14, 2));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x21);
+    assert_eq!(term.backend.cursor(), (14, 2));
+
+    term.events
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 63, 3));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.pane(), Pane::Ascii);
+    assert_eq!(editor.offset(), 0x34);
+
+    // Separators and the offset column don't move the cursor
+    term.events
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 12, 0))
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 3, 0));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x34);
+}
+
+#[test]
+fn l_wraps_to_next_line_and_scrolls() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys(&"j".repeat(8)).keys("$l");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x90);
+    assert_eq!(editor.start(), 1);
+    assert_eq!(term.backend.cursor(), (10, 8));
+}
+
+#[test]
+fn h_wraps_to_previous_line_and_scrolls() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events
+        .keys(&"j".repeat(9))
+        .keys(&"k".repeat(8))
+        .keys("h");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 0);
+    assert_eq!(editor.offset(), 0x0f);
+    assert_eq!(term.backend.cursor(), (56, 0));
+}
+
+#[test]
+fn h_and_l_stop_at_the_ends_of_the_file() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("h");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0);
+    assert_eq!(term.backend.cursor(), (10, 0));
+
+    term.events.keys("G$ll");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 30 * 16 - 1);
+    assert_eq!(term.backend.cursor(), (56, 8));
+}
+
+#[test]
+fn dollar_stops_at_last_byte_of_partial_row() {
+    let mut data = content(2);
+    data.truncate(16 + 5);
+    let mut editor = Editor::new(data);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+
+    term.events.keys("j$");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 20);
+    assert_eq!(term.backend.cursor(), (23, 1));
+
+    // The last nibble of the last byte is as far right as l can go
+    term.events.keys("0llllllllllllll");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 20);
+    assert_eq!(term.backend.cursor(), (23, 1));
+}
+
+/// G and holding j have to agree on where the view ends up, whether the file
+/// is just shorter than, exactly as tall as, or just taller than the screen
+#[test]
+fn big_g_and_j_agree_around_screen_height() {
+    let rows = 10;
+    for lines in [rows - 1, rows, rows + 1] {
+        let (mut by_g, mut g_term) = setup(lines as usize, 80, rows);
+        g_term.events.keys("G");
+        g_term.run(&mut by_g).unwrap();
+
+        let (mut by_j, mut j_term) = setup(lines as usize, 80, rows);
+        j_term.events.keys(&"j".repeat(50));
+        j_term.run(&mut by_j).unwrap();
+
+        assert_eq!(by_g.start(), by_j.start(), "{lines} lines");
+        assert_eq!(
+            g_term.backend.lines(),
+            j_term.backend.lines(),
+            "{lines} lines"
+        );
+        assert_eq!(
+            g_term.backend.lines()[rows as usize - 1],
+            "Press 'q' to quit",
+            "{lines} lines"
+        );
+
+        let screen = g_term.backend.lines();
+        g_term.events.keys("kj");
+        g_term.run(&mut by_g).unwrap();
+        assert_eq!(g_term.backend.lines(), screen, "{lines} lines");
+    }
+}
+
+#[test]
+fn shrinking_keeps_cursor_byte_on_screen() {
+    let (mut editor, mut term) = setup(30, 80, 20);
+    term.events.keys(&"j".repeat(15)).keys("lll").resize(80, 6);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 15 * 16 + 1);
+    assert_eq!(editor.start(), 11);
+    assert_eq!(term.backend.cursor(), (14, 4));
+    assert_eq!(offset_of_row(&term, 4), "000000f0");
+}
+
+#[test]
+fn big_g_on_file_shorter_than_screen() {
+    let mut data = content(3);
+    data.truncate(2 * 16 + 5);
+    let mut editor = Editor::new(data);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+
+    term.events.keys("llG");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 0);
+    assert_eq!(editor.offset(), 2 * 16 + 4);
+    assert_eq!(term.backend.cursor(), (22, 2));
+
+    term.events.keys("g");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0);
+    assert_eq!(term.backend.cursor(), (10, 0));
+}
+
+#[test]
+fn count_before_big_g_goes_to_line() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("12G");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 12 * 16);
+    assert_eq!(editor.start(), 4);
+    assert_eq!(term.backend.cursor(), (10, 8));
+
+    // 0 continues a count but otherwise still goes to the start of the line
+    term.events.keys("ll10G");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 10 * 16);
+    term.events.keys("ll0");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 10 * 16);
+
+    // Counts don't carry over to later commands and stop at the last line
+    term.events.keys("3jG");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 30 * 16 - 1);
+    term.events.keys("999G");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 29 * 16);
+}
+
+#[test]
+fn growing_at_end_of_file_pulls_in_more_lines() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("G").resize(80, 20);
+    term.run(&mut editor).unwrap();
+    // 30 lines on 19 data rows, the screen is still full
+    assert_eq!(editor.start(), 11);
+    assert_eq!(offset_of_row(&term, 18), "000001d0");
+    assert_eq!(editor.offset(), 30 * 16 - 1);
+    assert_eq!(term.backend.cursor(), (55, 18));
+
+    term.events.resize(80, 40);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 0);
+    assert_eq!(term.backend.cursor(), (55, 29));
+}
+
+#[test]
+fn status_message_times_out() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    editor.post(Severity::Info, "Copied 4 bytes");
+    editor.draw(&mut term.backend).unwrap();
+    assert_eq!(term.backend.lines()[9], "Copied 4 bytes");
+
+    // Still up just before the timeout, and across other redraws
+    let set_at = Instant::now();
+    term.events.resize(80, 10);
+    term.run(&mut editor).unwrap();
+    editor
+        .idle(&mut term.backend, set_at + STATUS_TIMEOUT / 2)
+        .unwrap();
+    assert_eq!(term.backend.lines()[9], "Copied 4 bytes");
+
+    editor
+        .idle(&mut term.backend, set_at + STATUS_TIMEOUT)
+        .unwrap();
+    assert_eq!(editor.status(), None);
+    assert_eq!(term.backend.lines()[9], "Press 'q' to quit");
+    assert_eq!(term.backend.cursor(), (10, 0));
+}
+
+#[test]
+fn keys_clear_status_messages() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    editor.post(Severity::Info, "one");
+    editor.post(Severity::Info, "two");
+    editor.draw(&mut term.backend).unwrap();
+    term.events.keys("l");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.status(), None);
+    assert_eq!(term.backend.lines()[9], "Press 'q' to quit");
+
+    // The message from the key itself stays
+    term.events.keys("9999G");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 29 * 16);
+    assert_eq!(
+        term.backend.lines()[9],
+        "only 30 lines, went to the last one"
+    );
+    let warning = Severity::Warning.style();
+    assert_eq!(term.backend.cell(0, 9).style, warning);
+    assert_eq!(term.backend.cell(79, 9).style, Style::default());
+}
+
+#[test]
+fn status_messages_queue_up() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    editor.post(Severity::Error, "first");
+    editor.post(Severity::Info, "second");
+    editor.draw(&mut term.backend).unwrap();
+    assert_eq!(term.backend.lines()[9], "first");
+    assert_eq!(term.backend.cell(0, 9).style, Severity::Error.style());
+
+    let now = Instant::now();
+    editor
+        .idle(&mut term.backend, now + STATUS_TIMEOUT)
+        .unwrap();
+    assert_eq!(term.backend.lines()[9], "second");
+    assert_eq!(term.backend.cell(0, 9).style, Style::default());
+    editor
+        .idle(&mut term.backend, now + STATUS_TIMEOUT * 2)
+        .unwrap();
+    assert_eq!(term.backend.lines()[9], "Press 'q' to quit");
+}
+
+#[test]
+fn cursor_never_passes_the_last_byte() {
+    // Two full lines and 5 bytes on the third, on a screen with room to spare
+    let mut data = content(3);
+    data.truncate(2 * 16 + 5);
+    let mut editor = Editor::new(data);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+
+    term.events.keys("$jjjj");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 36);
+    assert_eq!(term.backend.cursor(), (23, 2));
+
+    term.events.keys("gG");
+    term.run(&mut editor).unwrap();
+    assert!(editor.offset() < 37);
+    assert_eq!(editor.start(), 0);
+
+    term.events.push(scroll(MouseEventKind::ScrollDown));
+    term.events.push(scroll(MouseEventKind::ScrollDown));
+    term.run(&mut editor).unwrap();
+    assert!(editor.offset() < 37);
+
+    // Clicking the blank area after the data snaps to the last byte
+    term.events
+        .keys("g")
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 40, 6));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 36);
+}
+
+#[test]
+fn empty_file_has_nowhere_to_go() {
+    let mut editor = Editor::new(vec![]);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+    term.events.keys("jjlllG$");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0);
+    assert_eq!(editor.start(), 0);
+    assert_eq!(term.backend.cursor().1, 0);
+}
+
+#[test]
+fn too_small_terminal_shows_a_note_instead() {
+    for (cols, rows) in [(19, 3), (20, 2), (80, 1), (1, 1), (0, 0)] {
+        let (mut editor, mut term) = setup(30, cols, rows);
+        let lines = term.backend.lines();
+        if rows > 0 {
+            assert!(
+                !lines.iter().any(|line| line.contains("00000000")),
+                "{cols}x{rows}"
+            );
+        }
+        if cols >= 32 {
+            assert_eq!(
+                lines[rows as usize / 2].trim(),
+                "terminal too small (need ≥ 20x3)"
+            );
+        }
+        // Movement is ignored, there's nowhere to show it
+        term.events.keys("jjlG");
+        term.run(&mut editor).unwrap();
+        assert_eq!(editor.offset(), 0, "{cols}x{rows}");
+    }
+}
+
+#[test]
+fn smallest_usable_terminal() {
+    let (mut editor, mut term) = setup(30, 20, 3);
+    let lines = term.backend.lines();
+    // Only whole cells are drawn
+    assert_eq!(lines[0], "00000000: 00 01 02");
+    assert_eq!(lines[1], "00000010: 10 11 12");
+    assert_eq!(lines[2], "Press 'q' to quit");
+
+    // The line scrolls sideways to the last byte
+    term.events.keys("jjjG");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.start(), 28);
+    assert_eq!(term.backend.lines()[1], "000001dd: dd de df");
+    assert_eq!(term.backend.cursor(), (16, 1));
+}
+
+#[test]
+fn recovers_when_terminal_grows_again() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("jj").resize(10, 2);
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[1].contains("terminal"));
+
+    term.events.resize(80, 10);
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[9], "Press 'q' to quit");
+    assert_eq!(term.backend.cursor(), (10, 2));
+}
+
+#[test]
+fn heat_map_colors_rare_bytes() {
+    let mut data = vec![0; 64];
+    data[5] = 0xff;
+    let mut editor = Editor::new(data);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+    term.events.keys("F");
+    term.run(&mut editor).unwrap();
+
+    let backend = &term.backend;
+    let rare = backend.cell(10 + 5 * 3, 0).style;
+    assert!(rare.bg.is_some());
+    assert_eq!(backend.cell(10 + 5 * 3 + 1, 0).style, rare);
+    assert_eq!(backend.cell(59 + 5, 0).style, rare);
+    assert_eq!(backend.cell(10, 0).style, Style::default());
+    assert_eq!(backend.lines()[0].len(), 75);
+
+    term.events.keys("F");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.cell(10 + 5 * 3, 0).style, Style::default());
+}
+
+#[test]
+fn vertical_movement_remembers_column_across_partial_row() {
+    let mut data = content(3);
+    data.truncate(2 * 16 + 8);
+    let mut editor = Editor::new(data);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+
+    term.events.key(KeyCode::Tab, KeyModifiers::NONE);
+    term.events.keys("j");
+    term.events.keys(&"l".repeat(12));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 16 + 12);
+
+    // Onto the short line the cursor snaps to its last byte...
+    term.events.keys("j");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 2 * 16 + 7);
+    assert_eq!(term.backend.cursor(), (59 + 7, 2));
+
+    // ...and going back up restores the column
+    term.events.keys("kk");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 12);
+
+    // A horizontal move forgets it
+    term.events.keys("jjhk");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 16 + 6);
+
+    // $ sticks to the ends of lines
+    term.events.keys("k$jj");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 2 * 16 + 7);
+    term.events.keys("k");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 16 + 15);
+}
+
+#[test]
+fn struct_layout_decodes_at_cursor() {
+    let fields = parse_layout("u16 be, u8[2]").unwrap();
+    let mut editor = Editor::new(content(2)).with_fields(fields);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+
+    term.events.keys("jS");
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.lines()[9],
+        "00000010: u16 4113, u8[2] [18, 19]"
+    );
+    assert_eq!(term.backend.cursor(), (10, 1));
+
+    term.events.keys("llllllS");
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.lines()[9],
+        "00000013: u16 4884, u8[2] [21, 22]"
+    );
+}
+
+#[test]
+fn struct_layout_missing() {
+    let (mut editor, mut term) = setup(2, 80, 10);
+    term.events.keys("S");
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.lines()[9],
+        "no struct layout, start with --struct LAYOUT"
+    );
+}
+
+/// Every data row can be reached, and none of them is ever drawn over the
+/// status line, whatever the height of the terminal
+#[test]
+fn data_area_matches_status_row_at_every_height() {
+    for rows in 3..=12u16 {
+        for lines in [1, rows as usize - 1, rows as usize, 40] {
+            let (mut editor, mut term) = setup(lines, 80, rows);
+            let data_rows = rows - 1;
+            term.events.keys(&"j".repeat(60));
+            term.run(&mut editor).unwrap();
+
+            let last_row = (lines as u16).min(data_rows) - 1;
+            assert_eq!(term.backend.cursor(), (10, last_row), "{rows} rows");
+            let screen = term.backend.lines();
+            assert_eq!(
+                screen[rows as usize - 1],
+                "Press 'q' to quit",
+                "{rows} rows"
+            );
+            assert_eq!(
+                offset_of_row(&term, last_row),
+                format!("{:08x}", (lines - 1) * 16),
+                "{rows} rows, {lines} lines"
+            );
+
+            term.events.keys("gG");
+            term.run(&mut editor).unwrap();
+            assert_eq!(term.backend.lines(), screen, "{rows} rows, {lines} lines");
+        }
+    }
+}
+
+#[test]
+fn narrow_terminal_drops_ascii_and_scrolls_sideways() {
+    let (mut editor, mut term) = setup(3, 40, 10);
+    assert_eq!(
+        term.backend.lines()[0],
+        "00000000: 00 01 02 03 04 05 06 07 08 09"
+    );
+
+    // Past the last drawn byte the line scrolls by one cell at a time
+    term.events.keys(&"l".repeat(21));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 10);
+    assert_eq!(
+        term.backend.lines()[0],
+        "00000001: 01 02 03 04 05 06 07 08 09 0a"
+    );
+    assert_eq!(term.backend.cursor(), (38, 0));
+
+    term.events.keys("j$");
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.lines()[1],
+        "00000016: 16 17 18 19 1a 1b 1c 1d 1e 1f"
+    );
+    assert_eq!(term.backend.cursor(), (38, 1));
+
+    // Clicks map onto the bytes actually drawn
+    term.events
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 13, 2));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 32 + 7);
+
+    term.events.keys("0");
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.lines()[0],
+        "00000000: 00 01 02 03 04 05 06 07 08 09"
+    );
+    assert_eq!(term.backend.cursor(), (10, 2));
+
+    // The ASCII pane comes back once there's room
+    term.events.resize(80, 10);
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.lines()[0],
+        "00000000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f  ................"
+    );
+}
+
+#[test]
+fn printable_policy_picks_ascii_glyphs() {
+    let data = b"a b~\xe9\x80\x7f".to_vec();
+    for (printable, ascii) in [
+        (Printable::Ascii, "a b~..."),
+        (Printable::Latin1, "a b~é.."),
+        (Printable::All, "a b~é€."),
+    ] {
+        let editor = Editor::new(data.clone()).with_printable(printable);
+        let mut term = FakeTerminal::new(80, 4);
+        editor.draw(&mut term.backend).unwrap();
+        let row: String = term.backend.lines()[0].chars().skip(59).collect();
+        assert_eq!(row, ascii, "{printable:?}");
+    }
+}
+
+#[test]
+fn e_cycles_string_encodings() {
+    let mut data = b"x".to_vec();
+    data.extend("Hi".encode_utf16().flat_map(u16::to_le_bytes));
+    data.extend([0, 0]);
+    let mut editor = Editor::new(data);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+
+    term.events.keys("lle");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[9], "ascii: \"H\" (1 bytes) [1/4]");
+    term.events.keys("ee");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[9], "utf-16le: \"Hi\" (4 bytes) [3/4]");
+    term.events.keys("ee");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[9], "ascii: \"H\" (1 bytes) [1/4]");
+
+    // Moving starts over
+    term.events.keys("ee0e");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[9], "ascii: \"xH\" (2 bytes) [1/4]");
+}
+
+#[test]
+fn wide_characters_fit_in_the_status_line() {
+    let mut editor = Editor::new(vec![b'A'; 64]);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+
+    // Every "AA" is U+4141, which takes two columns, so only 32 of them fit
+    // with what's around them. The fake gives each character one cell, so
+    // the rest of its row is whatever was on it before.
+    term.events.keys("eee");
+    term.run(&mut editor).unwrap();
+    let text = format!("utf-16le: \"{}\" (64", "\u{4141}".repeat(32));
+    let shown: String = term.backend.row_text(9).chars().take(48).collect();
+    assert_eq!(shown, text);
+}
+
+#[test]
+fn baseline_tints_changed_bytes() {
+    let mut data = vec![0; 32];
+    let baseline = data[..20].to_vec();
+    data[3] = 0xaa;
+    let mut editor = Editor::new(data).with_baseline(Some(baseline));
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+
+    let backend = &term.backend;
+    let changed = backend.cell(10 + 3 * 3, 0).style;
+    assert!(changed.fg.is_some());
+    assert_eq!(backend.cell(59 + 3, 0).style, changed);
+    assert_eq!(backend.cell(10 + 2 * 3, 0).style, Style::default());
+    // Bytes past the end of the baseline count as changed too
+    assert_eq!(backend.cell(10 + 3, 1).style, Style::default());
+    assert_eq!(backend.cell(10 + 4 * 3, 1).style, changed);
+
+    // Both the heat map and the tint show at once
+    term.events.keys("F");
+    term.run(&mut editor).unwrap();
+    let style = term.backend.cell(10 + 3 * 3, 0).style;
+    assert_eq!(style.fg, changed.fg);
+    assert!(style.bg.is_some());
+}
+
+#[test]
+fn queued_events_are_drawn_once() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    let flushes = term.backend.flushes();
+    term.events.keys(&"j".repeat(20));
+    for _ in 0..5 {
+        term.events.push(scroll(MouseEventKind::ScrollUp));
+    }
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.flushes(), flushes + 1);
+    assert_eq!(editor.start(), 0);
+    assert_eq!(editor.offset(), 20 * 16);
+    assert_eq!(offset_of_row(&term, 0), "00000000");
+}
+
+#[test]
+fn key_releases_are_ignored() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    let key = |c, kind| {
+        Event::Key(KeyEvent::new_with_kind(
+            KeyCode::Char(c),
+            KeyModifiers::NONE,
+            kind,
+        ))
+    };
+    term.events
+        .push(key('j', KeyEventKind::Press))
+        .push(key('j', KeyEventKind::Release))
+        .push(key('j', KeyEventKind::Repeat))
+        .push(key('j', KeyEventKind::Repeat))
+        .push(key('j', KeyEventKind::Release))
+        .push(key('q', KeyEventKind::Release));
+    assert_eq!(term.run(&mut editor).unwrap(), Outcome::Continue);
+    assert_eq!(editor.offset(), 3 * 16);
+}
+
+#[test]
+fn replayed_keys_drive_the_editor() {
+    let mut editor = Editor::new(content(30));
+    let mut backend = TestBackend::new(80, 10);
+    let keys = parse_keys("jj\nll<Tab>l\n12G<C-c>j").unwrap();
+    let mut events = Replay::new(keys, ScriptedEvents::new());
+    editor.draw(&mut backend).unwrap();
+
+    let outcome = loop {
+        match editor.tick(&mut backend, &mut events).unwrap() {
+            Outcome::Continue => {}
+            outcome => break outcome,
+        }
+    };
+    assert_eq!(outcome, Outcome::Quit);
+    assert_eq!(editor.pane(), Pane::Ascii);
+    assert_eq!(editor.offset(), 12 * 16);
+    // The j after Ctrl-C is never read
+    let j = Event::Key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE));
+    assert_eq!(events.next_event(Duration::ZERO).unwrap(), Some(j));
+}
+
+#[test]
+fn arrow_keys_match_letters() {
+    let (mut by_letter, mut letter_term) = setup(30, 80, 10);
+    let (mut by_key, mut key_term) = setup(30, 80, 10);
+    let ctrl = KeyModifiers::CONTROL;
+    let steps = [
+        ("j", KeyCode::Down, KeyModifiers::NONE),
+        ("j", KeyCode::Enter, KeyModifiers::NONE),
+        ("l", KeyCode::Right, KeyModifiers::NONE),
+        ("$", KeyCode::End, KeyModifiers::NONE),
+        ("k", KeyCode::Up, KeyModifiers::NONE),
+        ("h", KeyCode::Left, KeyModifiers::NONE),
+        ("0", KeyCode::Home, KeyModifiers::NONE),
+        ("G", KeyCode::End, ctrl),
+        ("g", KeyCode::Home, ctrl),
+        ("j", KeyCode::Down, KeyModifiers::NONE),
+    ];
+    // A count before G works the same for Ctrl-End
+    let steps = steps
+        .iter()
+        .map(|&(letter, code, mods)| ("", letter, code, mods));
+    for (count, letter, code, modifiers) in steps.chain([("3", "G", KeyCode::End, ctrl)]) {
+        letter_term.events.keys(count).keys(letter);
+        letter_term.run(&mut by_letter).unwrap();
+        key_term.events.keys(count).key(code, modifiers);
+        key_term.run(&mut by_key).unwrap();
+        assert_eq!(by_key.offset(), by_letter.offset(), "{count}{letter}");
+        assert_eq!(key_term.backend.lines(), letter_term.backend.lines());
+        assert_eq!(key_term.backend.cursor(), letter_term.backend.cursor());
+    }
+}
+
+#[test]
+fn ruler_marks_interval_offsets() {
+    let (_, term) = setup(40, 80, 30);
+    let marked: Vec<u16> = (0..29)
+        .filter(|&row| term.backend.cell(0, row).style != Style::default())
+        .collect();
+    assert_eq!(marked, [0, 16]);
+    assert_eq!(
+        term.backend.cell(7, 16).style,
+        term.backend.cell(0, 16).style
+    );
+    assert_eq!(term.backend.cell(8, 16).style, Style::default());
+
+    for (interval, expected) in [(64, vec![0, 4]), (24, vec![0, 1, 3, 4, 6, 7]), (0, vec![])] {
+        let editor = Editor::new(content(8)).with_ruler(interval);
+        let mut term = FakeTerminal::new(80, 10);
+        editor.draw(&mut term.backend).unwrap();
+        let marked: Vec<u16> = (0..9)
+            .filter(|&row| term.backend.cell(0, row).style != Style::default())
+            .collect();
+        assert_eq!(marked, expected, "every {interval} bytes");
+    }
+}
+
+#[test]
+fn command_line_runs_commands() {
+    let (mut editor, mut term) = setup(40, 80, 10);
+    term.events.keys(":goto 0x100");
+    term.run(&mut editor).unwrap();
+    // Typing goes to the prompt, not to the bindings
+    assert_eq!(editor.offset(), 0);
+    assert_eq!(term.backend.row_text(9).trim_end(), ":goto 0x100");
+    assert_eq!(term.backend.cursor(), (11, 9));
+
+    term.events.key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x100);
+    assert_eq!(offset_of_row(&term, 8), "00000100");
+    assert_eq!(term.backend.row_text(9).trim_end(), "Press 'q' to quit");
+
+    term.events
+        .keys(":cols 32")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x100);
+    // The same bytes stay at the top, now twice as many per line
+    assert_eq!(offset_of_row(&term, 0), "00000080");
+    assert_eq!(offset_of_row(&term, 1), "000000a0");
+    // 32 bytes are too wide for 80 columns, so the ASCII pane goes
+    assert!(term.backend.row_text(0).trim_end().ends_with("94 95 96"));
+
+    term.events
+        .keys(":goto 9999")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 40 * 16 - 1);
+    assert_eq!(
+        term.backend.row_text(9).trim_end(),
+        "0x270f is past the end, went to the last byte"
+    );
+
+    term.events
+        .keys(":q")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    assert_eq!(term.run(&mut editor).unwrap(), Outcome::Quit);
+}
+
+#[test]
+fn command_line_errors_keep_the_text() {
+    let (mut editor, mut term) = setup(10, 80, 10);
+    term.events
+        .keys(":goto zz")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.row_text(9).trim_end(),
+        ":goto zz  (invalid number 'zz')"
+    );
+    assert_eq!(term.backend.cursor(), (8, 9));
+
+    // Fixing the text clears the error
+    term.events
+        .key(KeyCode::Backspace, KeyModifiers::NONE)
+        .key(KeyCode::Backspace, KeyModifiers::NONE)
+        .keys("20");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(9).trim_end(), ":goto 20");
+
+    // Esc throws the command away and q is a key again
+    term.events.key(KeyCode::Esc, KeyModifiers::NONE).keys("j");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 16);
+    assert_eq!(term.backend.row_text(9).trim_end(), "Press 'q' to quit");
+
+    term.events
+        .keys(":w")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.row_text(9).starts_with(":w  ("));
+    term.events
+        .key(KeyCode::Char('c'), KeyModifiers::CONTROL)
+        .keys("q");
+    assert_eq!(term.run(&mut editor).unwrap(), Outcome::Quit);
+}
+
+#[test]
+fn help_screen_scrolls_and_restores_the_view() {
+    let (mut editor, mut term) = setup(30, 80, 6);
+    term.events.keys("jjl");
+    term.run(&mut editor).unwrap();
+    let screen = term.backend.lines();
+    let cursor = term.backend.cursor();
+
+    term.events.keys("?");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[0], "Movement");
+    assert!(term.backend.lines()[1].starts_with("  h <Left>"));
+    term.events.keys("jj");
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[0].starts_with("  j <Down>"));
+    // j scrolled the help, not the cursor
+    assert_eq!(editor.offset(), 32);
+
+    term.events.keys("x");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines(), screen);
+    assert_eq!(term.backend.cursor(), cursor);
+
+    term.events
+        .keys(":help")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[0], "Movement");
+    // q closes the help rather than quitting
+    term.events.keys("q");
+    assert_eq!(term.run(&mut editor).unwrap(), Outcome::Continue);
+    assert_eq!(term.backend.lines(), screen);
+}
+
+#[test]
+fn z_folds_runs_of_one_byte() {
+    let mut data = content(50);
+    data[2 * 16..40 * 16].fill(0);
+    let mut editor = Editor::new(data);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+
+    term.events.keys("z");
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.row_text(2).trim_end(),
+        "00000020: ... 0x260 bytes of 0x00 ..."
+    );
+    assert_eq!(offset_of_row(&term, 3), "00000280");
+
+    // A fold is a single row to move over, and a single step sideways
+    term.events.keys("jjj");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x280);
+    assert_eq!(term.backend.cursor(), (10, 3));
+    term.events.keys("k");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x20);
+    term.events.keys("ll");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x280);
+    term.events.keys("hh");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x20);
+    // The last screen ends on the last line
+    term.events.keys("G");
+    term.run(&mut editor).unwrap();
+    assert_eq!(offset_of_row(&term, 8), "00000310");
+    assert_eq!(editor.start(), 4);
+
+    term.events.keys("gjjo");
+    term.run(&mut editor).unwrap();
+    assert_eq!(offset_of_row(&term, 3), "00000030");
+    // Nothing is folded any more, so z folds everything again
+    term.events.keys("z");
+    term.run(&mut editor).unwrap();
+    assert_eq!(offset_of_row(&term, 3), "00000280");
+    term.events.keys("z");
+    term.run(&mut editor).unwrap();
+    assert_eq!(offset_of_row(&term, 3), "00000030");
+}
+
+#[test]
+fn pending_count_is_shown() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("12");
+    term.run(&mut editor).unwrap();
+    let status = term.backend.row_text(9);
+    assert!(status.starts_with("Press 'q' to quit "));
+    assert!(status.ends_with(" 12"), "{status:?}");
+    assert_eq!(status.chars().count(), 80);
+
+    term.events.keys("G");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 12 * 16);
+    assert_eq!(term.backend.row_text(9).trim_end(), "Press 'q' to quit");
+
+    // Esc drops the count
+    term.events.keys("3").key(KeyCode::Esc, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(9).trim_end(), "Press 'q' to quit");
+    term.events.keys("G");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 30 * 16 - 1);
+}
+
+#[test]
+fn counts_repeat_movement() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    term.events.keys("3j");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 3 * 16);
+
+    // Two nibbles make a byte in the hex pane
+    term.events.keys("4l2k");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 16 + 2);
+
+    // Enter and the arrow keys go by the count the same way
+    term.events
+        .keys("12")
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys("3")
+        .key(KeyCode::Left, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 13 * 16);
+    assert_eq!(editor.start(), 13 + 1 - 9);
+
+    // Counts past either end stop there
+    term.events.keys("99k999999h");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0);
+    term.events.keys("99j");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 29 * 16);
+}
+
+#[test]
+fn questions_block_keys_until_answered() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    editor.post(Severity::Info, "earlier message");
+    // The answer can take along whatever it needs
+    let verb = String::from("answered");
+    editor.ask(Question::yes_no("Really?", false), move |editor, choice| {
+        editor.post(Severity::Info, format!("{verb} {choice:?}"));
+        None
+    });
+    editor.draw(&mut term.backend).unwrap();
+    assert_eq!(term.backend.lines()[9], "Really? (y)es/(N)o");
+    assert_eq!(term.backend.cursor(), (18, 9));
+
+    term.events.keys("jjxq");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0);
+    assert_eq!(term.backend.lines()[9], "Really? (y)es/(N)o");
+
+    // So do clicks and the wheel, but the question follows a resize
+    term.events
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 13, 2))
+        .push(mouse(MouseEventKind::ScrollDown, 13, 2))
+        .resize(60, 8);
+    term.run(&mut editor).unwrap();
+    assert_eq!((editor.offset(), editor.start()), (0, 0));
+    assert_eq!(term.backend.lines()[7], "Really? (y)es/(N)o");
+    term.events.resize(80, 10);
+    term.run(&mut editor).unwrap();
+
+    // The answer comes after whatever was up before the question
+    term.events.key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[9], "earlier message");
+    assert_eq!(term.backend.cursor(), (10, 0));
+    term.events.keys("j");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 16);
+}
+
+#[test]
+fn questions_with_labeled_choices() {
+    let (mut editor, mut term) = setup(30, 80, 10);
+    let choices = vec![
+        Choice {
+            label: "overwrite",
+            key: 'o',
+        },
+        Choice {
+            label: "reload",
+            key: 'r',
+        },
+        Choice {
+            label: "cancel",
+            key: 'c',
+        },
+    ];
+    let ask = |editor: &mut Editor| {
+        editor.ask(
+            Question::new("File changed.", choices.clone(), 2),
+            |editor, choice| match choice {
+                Some(0) => Some(Outcome::Quit),
+                _ => {
+                    editor.post(Severity::Info, format!("answered {choice:?}"));
+                    None
+                }
+            },
+        );
+    };
+    ask(&mut editor);
+    term.events.keys("r");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.status(), Some("answered Some(1)"));
+
+    // The next key clears the message, the next answer would queue behind it
+    term.events.keys("l");
+    term.run(&mut editor).unwrap();
+    ask(&mut editor);
+    term.events.key(KeyCode::Esc, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.status(), Some("answered None"));
+
+    ask(&mut editor);
+    term.events.keys("O");
+    assert_eq!(term.run(&mut editor).unwrap(), Outcome::Quit);
+}
+
+#[test]
+fn help_search_highlights_matches() {
+    let (mut editor, mut term) = setup(30, 80, 40);
+    term.events.keys("?/cols");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[39], "/cols");
+    assert_eq!(term.backend.cursor(), (5, 39));
+
+    term.events.key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    let lines = term.backend.lines();
+    let row = lines
+        .iter()
+        .position(|l| l.starts_with("  :cols N"))
+        .unwrap() as u16;
+    assert_eq!(term.backend.cell(3, row).style.bg, Some(Color::Yellow));
+    assert_eq!(term.backend.cell(2, row).style, Style::default());
+    // The search prompt took the q, the help is still open
+    term.events.keys("/q").key(KeyCode::Esc, KeyModifiers::NONE);
+    assert_eq!(term.run(&mut editor).unwrap(), Outcome::Continue);
+    assert!(term.backend.lines()[39].starts_with("j/k scroll"));
+}
+
+#[test]
+fn search_previews_matches_as_they_are_typed() {
+    let (mut editor, mut term) = setup(40, 80, 10);
+    // A lone nibble isn't a byte yet, so nothing moves
+    term.events.keys("jj/1");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x20);
+    assert_eq!(term.backend.row_text(9).trim_end(), "/1");
+    assert_eq!(term.backend.cursor(), (2, 9));
+
+    // The first match after the cursor is brought into view and highlighted
+    term.events.keys("2 1");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x112);
+    assert_eq!(offset_of_row(&term, 8), "00000110");
+    assert_eq!(term.backend.cell(16, 8).style.bg, Some(Color::Yellow));
+    assert_eq!(term.backend.cell(19, 8).style, Style::default());
+    term.events.keys("3");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.cell(19, 8).style.bg, Some(Color::Yellow));
+    assert_eq!(term.backend.cursor(), (6, 9));
+
+    // Esc puts everything back as it was
+    term.events.key(KeyCode::Esc, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x20);
+    assert_eq!(editor.start(), 0);
+    assert_eq!(term.backend.cursor(), (10, 2));
+    assert_eq!(term.backend.row_text(9).trim_end(), "Press 'q' to quit");
+
+    // Enter stays on the match, even when typed before the preview ran
+    term.events
+        .keys("/ff")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0xff);
+    assert_eq!(term.backend.row_text(9).trim_end(), "Press 'q' to quit");
+    // Accepted, it's one of the marked matches now
+    assert_eq!(term.backend.cell(55, 8).style.bg, Some(Color::DarkYellow));
+
+    // The ASCII pane searches for text
+    term.events
+        .key(KeyCode::Tab, KeyModifiers::NONE)
+        .keys("/AB")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x141);
+}
+
+#[test]
+fn search_reports_bad_patterns() {
+    let (mut editor, mut term) = setup(4, 80, 10);
+    term.events.keys("j/4x");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(9).trim_end(), "/4x  (not hex)");
+    term.events
+        .key(KeyCode::Backspace, KeyModifiers::NONE)
+        .keys("0");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(9).trim_end(), "/40  (not found)");
+
+    term.events.key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x10);
+    assert_eq!(term.backend.row_text(9).trim_end(), "40: not found");
+}
+
+#[test]
+fn prompts_recall_their_history() {
+    let (editor, mut term) = setup(40, 80, 10);
+    let mut history = Histories::default();
+    history.command.push("goto 0x20");
+    let mut editor = editor.with_history(history);
+    let enter = |term: &mut FakeTerminal, keys: &str| {
+        term.events
+            .keys(keys)
+            .key(KeyCode::Enter, KeyModifiers::NONE);
+    };
+    // Failed commands are kept too
+    enter(&mut term, ":cols x");
+    term.events.key(KeyCode::Esc, KeyModifiers::NONE);
+    enter(&mut term, ":goto 0x30");
+    enter(&mut term, ":goto 0x30");
+    enter(&mut term, "/ff");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.history().command.get(0), Some("goto 0x30"));
+    assert_eq!(editor.history().command.get(1), Some("cols x"));
+
+    let status = |term: &FakeTerminal| term.backend.row_text(9).trim_end().to_string();
+    let up = |term: &mut FakeTerminal, editor: &mut Editor| {
+        term.events.key(KeyCode::Up, KeyModifiers::NONE);
+        term.run(editor).unwrap();
+    };
+    let down = |term: &mut FakeTerminal, editor: &mut Editor| {
+        term.events.key(KeyCode::Down, KeyModifiers::NONE);
+        term.run(editor).unwrap();
+    };
+    term.events.keys(":");
+    up(&mut term, &mut editor);
+    assert_eq!(status(&term), ":goto 0x30");
+    up(&mut term, &mut editor);
+    assert_eq!(status(&term), ":cols x");
+    up(&mut term, &mut editor);
+    up(&mut term, &mut editor);
+    assert_eq!(status(&term), ":goto 0x20");
+    down(&mut term, &mut editor);
+    down(&mut term, &mut editor);
+    assert_eq!(status(&term), ":goto 0x30");
+    down(&mut term, &mut editor);
+    assert_eq!(status(&term), ":");
+
+    // Only entries with the typed text in them come up
+    term.events.keys("go");
+    up(&mut term, &mut editor);
+    up(&mut term, &mut editor);
+    assert_eq!(status(&term), ":goto 0x20");
+    // Nothing runs until Enter
+    assert_eq!(editor.offset(), 0xff);
+
+    // The search prompt has a history of its own
+    term.events.key(KeyCode::Esc, KeyModifiers::NONE).keys("/");
+    up(&mut term, &mut editor);
+    assert_eq!(status(&term), "/ff");
+    up(&mut term, &mut editor);
+    assert_eq!(status(&term), "/ff");
+}
+
+#[test]
+fn x_shows_nibbles_apart() {
+    let (mut editor, mut term) = setup(4, 100, 10);
+    term.events.keys("lx");
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[0].starts_with("00000000: 0 0 0 1 0 2 "));
+    assert!(term.backend.lines()[1].ends_with("1 e 1 f  ................"));
+    assert_eq!(term.backend.cursor(), (12, 0));
+    term.events.keys("l");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.cursor(), (14, 0));
+
+    // Clicks land on the nibble under them, not on the spaces
+    term.events
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 20, 1));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x12);
+    assert_eq!(term.backend.cursor(), (20, 1));
+
+    // Too narrow for the ASCII pane, as the wider cells need more room
+    term.events.push(Event::Resize(80, 10));
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[0].trim_end().len(), 73);
+    term.events.keys("x");
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[0].ends_with("0e 0f  ................"));
+}
+
+#[test]
+fn messages_command_shows_the_log() {
+    let (mut editor, mut term) = setup(4, 80, 10);
+    term.events
+        .keys(":mes")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(9).trim_end(), "no messages yet");
+
+    editor.post(Severity::Error, "cannot read the history");
+    // The key that clears a message doesn't lose it
+    term.events
+        .keys("S:messages")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    let lines = term.backend.lines();
+    assert_eq!(lines[0].trim_end(), "info     no messages yet");
+    assert_eq!(lines[1].trim_end(), "error    cannot read the history");
+    assert!(lines[2].trim_end().starts_with("warning  no struct layout"));
+    assert!(lines[3].trim().is_empty());
+
+    term.events.keys("q");
+    term.run(&mut editor).unwrap();
+    assert_eq!(offset_of_row(&term, 0), "00000000");
+}
+
+#[test]
+fn brackets_stop_at_matches_and_changes() {
+    let (mut editor, mut term) = setup(8, 80, 10);
+    term.events.keys("]");
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.row_text(9).trim_end(),
+        "nothing to stop at, search with / or start with --baseline"
+    );
+
+    let mut baseline = content(8);
+    baseline[0x30..0x34].fill(0);
+    baseline[0x60] = 0;
+    let mut editor = editor.with_baseline(Some(baseline));
+    term.events
+        .keys("/40")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x40);
+
+    let mut stops = Vec::new();
+    for key in "]][[".chars() {
+        term.events.keys(&key.to_string());
+        term.run(&mut editor).unwrap();
+        stops.push(editor.offset());
+    }
+    assert_eq!(stops, [0x60, 0x30, 0x60, 0x40]);
+
+    term.events
+        .keys(":stops changes")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.row_text(9).trim_end(),
+        "] and [ stop at changes"
+    );
+    term.events.keys("]");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x60);
+}
+
+#[test]
+fn offset_is_repeated_on_the_right() {
+    let mut data = content(2);
+    data.push(0x41);
+    let mut editor = Editor::new(data).with_right_offset(true);
+    let mut term = FakeTerminal::new(90, 10);
+    editor.draw(&mut term.backend).unwrap();
+    let lines = term.backend.lines();
+    assert!(lines[0].ends_with("0e 0f  ................  00000000"));
+    assert!(lines[2].ends_with("A                 00000020"));
+
+    // Not enough room, it goes before the ASCII pane does
+    term.events.push(Event::Resize(84, 10));
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[0].ends_with("0e 0f  ................"));
+}
+
+#[test]
+fn status_format_follows_the_cursor() {
+    let (format, _) = StatusFormat::parse("{file} {offset} {byte} ({percent}%)");
+    let mut editor = Editor::new(content(4))
+        .with_status_format(Some(format))
+        .with_file_name("/some/where/data.bin");
+    let mut term = FakeTerminal::new(30, 5);
+    editor.draw(&mut term.backend).unwrap();
+    // The file name gives way to the other fields
+    assert_eq!(term.backend.lines()[4], "...here/data.bin 0x0 0x00 (1%)");
+
+    term.events.keys("jl");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[4], "...re/data.bin 0x10 0x10 (26%)");
+    // Messages still come first
+    term.events.keys("S");
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[4].starts_with("no struct layout"));
+}
+
+#[test]
+fn record_jumps_by_record_size() {
+    let mut editor = Editor::new(content(40 * 16));
+    let mut term = FakeTerminal::new(80, 10);
+    term.events
+        .keys(":record 2")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0);
+    assert!(term.backend.lines()[9].starts_with("no record size"));
+
+    term.events
+        .keys(":recsize 0x30")
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys(":record 2")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x60);
+    assert!(term.backend.lines()[9].ends_with(" record 2"));
+
+    // The readout follows the cursor, and the count still goes after it
+    term.events.keys("k3");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x50);
+    assert!(term.backend.lines()[9].ends_with(" record 1 3"));
+}
+
+#[test]
+fn angle_brackets_change_the_line_length() {
+    let (mut editor, mut term) = setup(40, 80, 10);
+    term.events
+        .keys(":goto 0x22")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.events.keys("<");
+    term.run(&mut editor).unwrap();
+    assert_eq!(offset_of_row(&term, 1), "0000000f");
+    assert_eq!(term.backend.row_text(9).trim_end(), "15 bytes per line");
+    // The cursor keeps its byte, on whatever line it's now on
+    assert_eq!(editor.offset(), 0x22);
+    assert_eq!(term.backend.cursor(), (10 + 4 * 3, 2));
+
+    term.events.keys("9>");
+    term.run(&mut editor).unwrap();
+    assert_eq!(offset_of_row(&term, 1), "00000018");
+    assert_eq!(editor.offset(), 0x22);
+    assert_eq!(term.backend.cursor(), (10 + 10 * 3, 1));
+
+    term.events.keys("99<");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(9).trim_end(), "1 byte per line");
+}
+
+#[test]
+fn prompts_edit_anywhere_on_the_line() {
+    let (mut editor, mut term) = setup(40, 20, 10);
+    term.events
+        .keys(":goto 10")
+        .key(KeyCode::Left, KeyModifiers::NONE)
+        .key(KeyCode::Left, KeyModifiers::NONE)
+        .keys("0x");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(9).trim_end(), ":goto 0x10");
+    assert_eq!(term.backend.cursor(), (8, 9));
+
+    // Longer than the screen, the start scrolls out of view
+    term.events
+        .key(KeyCode::End, KeyModifiers::NONE)
+        .keys(" and some more");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(9), ":0x10 and some more ");
+    assert_eq!(term.backend.cursor(), (19, 9));
+
+    term.events
+        .key(KeyCode::Char('w'), KeyModifiers::CONTROL)
+        .key(KeyCode::Char('w'), KeyModifiers::CONTROL)
+        .key(KeyCode::Char('w'), KeyModifiers::CONTROL)
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x10);
+
+    // Ctrl-U clears what's before the cursor, the same on the search prompt
+    term.events
+        .keys("/4142")
+        .key(KeyCode::Char('u'), KeyModifiers::CONTROL)
+        .keys("00");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(9).trim_end(), "/00");
+}
+
+#[test]
+fn screenshot_saves_the_screen() {
+    let (mut editor, mut term) = setup(4, 80, 6);
+    let dir = std::env::temp_dir();
+    let svg = dir.join(format!("hex-editor-shot-{}.svg", std::process::id()));
+    let text = dir.join(format!("hex-editor-shot-{}.txt", std::process::id()));
+    for path in [&svg, &text] {
+        term.events
+            .keys(&format!(":screenshot {}", path.display()))
+            .key(KeyCode::Enter, KeyModifiers::NONE);
+    }
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[5].starts_with("saved the screen to"));
+
+    let svg_text = std::fs::read_to_string(&svg).unwrap();
+    assert!(svg_text.starts_with("<svg "));
+    assert!(svg_text.contains(">00000000</text>"), "{svg_text}");
+    assert!(svg_text.contains(">00000010: 10 11 "), "{svg_text}");
+    let ansi = std::fs::read_to_string(&text).unwrap();
+    let lines: Vec<&str> = ansi.lines().collect();
+    assert_eq!(lines.len(), 6);
+    // The same lines the screen shows, the ruler's offset in its color
+    assert!(
+        lines[0].starts_with("\x1b[36m00000000\x1b[0m: 00 01"),
+        "{ansi}"
+    );
+    assert!(lines[1].starts_with("00000010: 10 11"), "{ansi}");
+    assert_eq!(lines[5], "Press 'q' to quit");
+    std::fs::remove_file(svg).unwrap();
+    std::fs::remove_file(text).unwrap();
+
+    term.events
+        .keys(":screenshot /no/such/dir/shot.svg")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[5].starts_with("couldn't write /no/such/dir/shot.svg"));
+}
+
+#[test]
+fn screenshot_asks_before_overwriting() {
+    let (mut editor, mut term) = setup(4, 80, 6);
+    let path = std::env::temp_dir().join(format!("hex-editor-kept-{}.txt", std::process::id()));
+    std::fs::write(&path, "keep me").unwrap();
+    let command = format!(":screenshot {}", path.display());
+    term.events
+        .keys(&command)
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    let status = &term.backend.lines()[5];
+    assert!(
+        status.starts_with("Overwrite ") && status.ends_with("(y)es/(N)o"),
+        "{status}"
+    );
+
+    // No is the default, and Esc says no too
+    term.events
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys(&command)
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .key(KeyCode::Esc, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[5].starts_with("kept "));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep me");
+
+    term.events
+        .keys(&command)
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys("y");
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[5].starts_with("saved the screen to"));
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert!(saved.contains("00000010: 10 11"), "{saved}");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn base64_saves_the_scope_or_everything() {
+    let (mut editor, mut term) = setup(4, 80, 6);
+    let path = std::env::temp_dir().join(format!("hex-editor-{}.b64", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let command = format!(":base64 {}", path.display());
+    term.events
+        .keys(":scope 0..3")
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys(&command)
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[5].starts_with("saved 3 bytes as base64 to "));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "AAEC\n");
+
+    // Like a screenshot, it asks before writing over a file
+    term.events
+        .keys(":scope")
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys(&command)
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[5].starts_with("Overwrite "));
+    term.events.keys("y");
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[5].starts_with("saved 64 bytes as base64 to "));
+    let saved = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(saved, base64::encode(&content(4)) + "\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn byte_readout_follows_the_cursor() {
+    let mut editor = Editor::new(b"A\x00".to_vec()).with_byte_readout(true);
+    let mut term = FakeTerminal::new(80, 5);
+    editor.draw(&mut term.backend).unwrap();
+    assert!(term.backend.lines()[4].ends_with(" 0x41 65 0o101 0b01000001 'A'"));
+
+    let flushes = term.backend.flushes();
+    term.events.keys("ll");
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[4].ends_with(" 0x00 0 0o0 0b00000000 ^@"));
+    // Only the status line was drawn again
+    assert_eq!(term.backend.flushes(), flushes + 1);
+    assert!(term.backend.lines()[0].starts_with("00000000: 41 00 "));
+
+    // A prompt gets the whole line, scrolled to keep the cursor on it
+    term.events.keys(&format!(":goto 0x{}", "0".repeat(90)));
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.row_text(4), format!(":{} ", "0".repeat(78)));
+    assert_eq!(term.backend.cursor(), (79, 4));
+    term.events.key(KeyCode::Esc, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[4].ends_with(" ^@"));
+
+    let editor = Editor::new(Vec::new()).with_byte_readout(true);
+    editor.draw(&mut term.backend).unwrap();
+    assert!(term.backend.lines()[4].ends_with("  --"));
+}
+
+#[test]
+fn time_decodes_timestamps_at_the_cursor() {
+    let mut content = vec![0; 4];
+    content.extend(1_709_211_909u32.to_be_bytes());
+    content.extend([0; 4]);
+    let mut editor = Editor::new(content);
+    let mut term = FakeTerminal::new(200, 5);
+    term.events
+        .keys(":goto 4")
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys(":time be")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    let status = &term.backend.lines()[4];
+    assert!(
+        status.starts_with("00000004: unix32 2024-02-29 13:05:09 UTC, unix64 "),
+        "{status}"
+    );
+    // The same bytes read as 64 bits are far too big for a date
+    assert!(
+        status.ends_with(", filetime out of range (0x65e0810500000000)"),
+        "{status}"
+    );
+
+    // Only four bytes are left, so only the 32 bit one fits
+    term.events
+        .keys(":goto 8")
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys(":time")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(
+        term.backend.lines()[4],
+        "00000008: unix32 1970-01-01 00:00:00 UTC"
+    );
+}
+
+#[test]
+fn matches_stay_marked_until_nohl() {
+    let mut content = content(0x20);
+    content[0x1f0..0x1f2].copy_from_slice(&[0x10, 0x11]);
+    let mut editor = Editor::new(content);
+    let mut term = FakeTerminal::new(80, 10);
+    term.events
+        .keys("/10 11")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x10);
+    // Every match on screen is marked, in both panes
+    let marked = |term: &FakeTerminal, col, row| term.backend.cell(col, row).style.bg;
+    assert_eq!(marked(&term, 10, 1), Some(Color::DarkYellow));
+    assert_eq!(marked(&term, 13, 1), Some(Color::DarkYellow));
+    assert_eq!(marked(&term, 16, 1), None);
+    assert_eq!(marked(&term, 60, 1), Some(Color::DarkYellow));
+    assert_eq!(marked(&term, 61, 1), None);
+
+    // Scrolled to, matches further on are marked too
+    term.events.keys("G");
+    term.run(&mut editor).unwrap();
+    assert_eq!(offset_of_row(&term, 8), "000001f0");
+    assert_eq!(marked(&term, 10, 8), Some(Color::DarkYellow));
+    assert_eq!(marked(&term, 59, 8), Some(Color::DarkYellow));
+
+    term.events
+        .keys(":nohl")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(marked(&term, 10, 8), None);
+    // The pattern is still there for ]
+    term.events.keys("]");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x10);
+}
+
+#[test]
+fn scope_limits_searches() {
+    let mut editor = Editor::new(content(0x20));
+    let mut term = FakeTerminal::new(80, 10);
+    term.events
+        .keys(":scope 0x100..0x180")
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys("/10 11")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    // The match at 0x10 comes first in the data but not in the scope
+    assert_eq!(editor.offset(), 0x110);
+    assert!(term.backend.lines()[9].ends_with("[0x100..0x180]"));
+
+    // ] and [ go round the scope instead of leaving it
+    term.events.keys("]");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x110);
+    term.events.keys("gg[");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x110);
+
+    term.events
+        .keys("/20 21 22")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x120);
+    term.events
+        .keys("/a0 a1")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[9].starts_with("a0 a1: not found in the scope"));
+
+    term.events
+        .keys(":scope")
+        .key(KeyCode::Enter, KeyModifiers::NONE)
+        .keys("]");
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x20);
+    assert!(!term.backend.lines()[9].contains("[0x"));
+}
+
+#[test]
+fn verify_compares_the_sha256() {
+    let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    let mut editor = Editor::new(b"abc".to_vec());
+    let mut term = FakeTerminal::new(80, 10);
+    term.events
+        .keys(&format!(":verify sha256 {abc}"))
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    assert!(term.backend.lines()[9].starts_with("MATCH"));
+
+    let mut editor = Editor::new(b"abd".to_vec());
+    term.events
+        .keys(&format!(":verify sha256 {abc}"))
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    let status = &term.backend.lines()[9];
+    assert!(status.starts_with("MISMATCH (got "), "{status}");
+    assert_eq!(term.backend.cell(0, 9).style, Severity::Error.style());
+}
+
+#[test]
+fn bitmap_draws_the_data_as_a_picture() {
+    let mut content = vec![0; 0x40];
+    content[0x10..0x12].copy_from_slice(&[0xf0, 0x0f]);
+    let mut editor = Editor::new(content);
+    let mut term = FakeTerminal::new(80, 10);
+    term.events
+        .keys("j:bitmap 8")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    // From the cursor, a bit a pixel and two rows of pixels a character
+    let lines = term.backend.lines();
+    assert_eq!(lines[0], "▀▀▀▀▄▄▄▄");
+    assert_eq!(lines[1], "");
+    assert!(lines[9].starts_with("bitmap from 0x10, 8 pixels wide"));
+
+    term.events.keys("j");
+    term.run(&mut editor).unwrap();
+    assert_eq!(term.backend.lines()[0], "");
+    // Any key that doesn't scroll closes it, and does nothing else
+    term.events.keys("x");
+    term.run(&mut editor).unwrap();
+    assert_eq!(offset_of_row(&term, 0), "00000000");
+    assert_eq!(editor.offset(), 0x10);
+
+    term.events
+        .keys(":bitmap 2 grey")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    let cell = |col| term.backend.cell(col, 0).style;
+    assert_eq!(cell(0).fg, Some(Color::AnsiValue(254)));
+    assert_eq!(cell(0).bg, Some(Color::AnsiValue(16)));
+    assert_eq!(cell(1).fg, Some(Color::AnsiValue(233)));
+}
+
+#[test]
+fn ascii_pane_can_go_on_the_left() {
+    let mut content = content(2);
+    content[0x11] = 0xe9;
+    let mut editor = Editor::new(content)
+        .with_printable(Printable::Latin1)
+        .with_ascii_left(true);
+    let mut term = FakeTerminal::new(80, 10);
+    editor.draw(&mut term.backend).unwrap();
+    let lines = term.backend.lines();
+    assert_eq!(
+        lines[0],
+        "00000000: ................  00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f"
+    );
+    assert!(lines[1].starts_with("00000010: .é..............  10 e9 12"));
+    // Clicking the glyph and the hex land on the same byte
+    term.events
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 11, 1));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.offset(), 0x11);
+    assert_eq!(editor.pane(), Pane::Ascii);
+    assert_eq!(term.backend.cursor(), (11, 1));
+    term.events
+        .push(mouse(MouseEventKind::Down(MouseButton::Left), 32, 1));
+    term.run(&mut editor).unwrap();
+    assert_eq!(editor.pane(), Pane::Hex);
+    assert_eq!(editor.offset(), 0x11);
+    assert_eq!(term.backend.cursor(), (32, 1));
+
+    // Highlights land on the hex after glyphs that take more than a byte
+    term.events
+        .keys("/e9 12")
+        .key(KeyCode::Enter, KeyModifiers::NONE);
+    term.run(&mut editor).unwrap();
+    let cell = term.backend.cell(31, 1);
+    assert_eq!((cell.symbol, cell.style.bg), ('e', Some(Color::DarkYellow)));
+    assert_eq!(term.backend.lines()[1], lines[1]);
+}